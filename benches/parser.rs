@@ -0,0 +1,214 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use nmea::NmeaParser;
+use std::hint::black_box;
+
+const LINES: [&str; 5] = [
+    "$GPFPD,2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,-308580.94,0.003,-0.033,-3243.491,10.191,15,18,04*63\r\n",
+    "$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n",
+    "$GPGGA,060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42\r\n",
+    "$GPCHC,2200,202733.70,258.15,-0.33,0.61,-0.03,0.03,-0.07,0.0058,-0.0111,1.0004,31.18826868,121.60418588,16.27,-0.011,-0.002,0.002,0.011,14,17,42,0,0002*67\r\n",
+    "$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n",
+];
+
+/// 每次从外设读到的字节数
+const CHUNK: usize = 64;
+
+/// 最坏情况：语句几乎占满缓冲区，旧实现每条语句都要挪动一次缓冲区
+const LONG_LINES: [&str; 2] = [LINES[3], LINES[0]];
+
+/// 刚好容纳 [`LONG_LINES`] 中最长语句的缓冲区大小
+const NEARLY_FULL: usize = 160;
+
+fn stream(lines: &[&str]) -> Vec<u8> {
+    lines
+        .iter()
+        .cycle()
+        .take(1000)
+        .flat_map(|l| l.bytes())
+        .collect()
+}
+
+/// 改写前的实现：每次接收前把剩余数据挪到开头，逐字节扫描
+mod legacy {
+    use nmea::NmeaLine;
+
+    pub struct Parser<const LEN: usize> {
+        buf: [u8; LEN],
+        r: usize,
+        c: usize,
+        w: usize,
+    }
+
+    impl<const LEN: usize> Default for Parser<LEN> {
+        fn default() -> Self {
+            Self {
+                buf: [0; LEN],
+                r: 0,
+                c: 0,
+                w: 0,
+            }
+        }
+    }
+
+    impl<const LEN: usize> Parser<LEN> {
+        pub fn as_buf(&mut self) -> &mut [u8] {
+            if self.r > 0 {
+                self.buf.copy_within(self.r..self.w, 0);
+                self.w -= self.r;
+            }
+            self.c = self.c.saturating_sub(self.r);
+            self.r = 0;
+            &mut self.buf[self.w..]
+        }
+
+        pub fn notify_received(&mut self, n: usize) {
+            self.w += n;
+        }
+
+        fn move_on(&mut self) -> bool {
+            while self.r < self.w && self.buf[self.r] != b'$' {
+                self.r += 1;
+            }
+            if self.r == self.w {
+                return false;
+            }
+            if self.r >= self.c {
+                self.c = self.r + 1;
+            }
+            while self.c < self.w && self.buf[self.c] != b'*' {
+                self.c += 1;
+            }
+            self.c < self.w
+        }
+    }
+
+    impl<const LEN: usize> Iterator for Parser<LEN> {
+        type Item = (NmeaLine, u8);
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if !(self.move_on() && self.w >= self.c + 3) {
+                    if self.r == 0 && self.w == LEN {
+                        self.r = 1;
+                        continue;
+                    }
+                    return None;
+                }
+                let (r, c) = (self.r, self.c);
+                self.r = c + 3;
+                self.c += 4;
+                let body = &self.buf[r + 1..c];
+                let expected = if body.starts_with(b"cmd") {
+                    0xff
+                } else {
+                    body.iter().fold(0, |sum, it| sum ^ it)
+                };
+                let received = std::str::from_utf8(&self.buf[c + 1..c + 3])
+                    .ok()
+                    .and_then(|cs| u8::from_str_radix(cs, 16).ok());
+                if received != Some(expected) {
+                    continue;
+                }
+                if let Ok(line) = std::str::from_utf8(body).unwrap().parse() {
+                    return Some((line, expected));
+                }
+            }
+        }
+    }
+}
+
+/// 模拟外设分块到达，每次最多读满缓冲区的剩余空间，每块到达后取尽缓冲区
+macro_rules! feed {
+    ($parser:expr, $stream:expr, $drain:expr) => {{
+        let mut parser = $parser;
+        let mut count = 0usize;
+        let mut data: &[u8] = $stream;
+        while !data.is_empty() {
+            let buf = parser.as_buf();
+            let n = buf.len().min(data.len()).min(CHUNK);
+            buf[..n].copy_from_slice(&data[..n]);
+            parser.notify_received(n);
+            data = &data[n..];
+            count += $drain(&mut parser);
+        }
+        count
+    }};
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let (stream, long) = (stream(&LINES), stream(&LONG_LINES));
+    let mut group = c.benchmark_group("parser");
+    group.throughput(Throughput::Bytes(stream.len() as u64));
+
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            feed!(
+                legacy::Parser::<256>::default(),
+                black_box(&stream),
+                |p: &mut legacy::Parser<256>| p.map(black_box).count()
+            )
+        })
+    });
+    group.bench_function("ring", |b| {
+        b.iter(|| {
+            feed!(
+                NmeaParser::<256>::default(),
+                black_box(&stream),
+                |p: &mut NmeaParser<256>| p.map(black_box).count()
+            )
+        })
+    });
+    group.bench_function("ring_ref", |b| {
+        b.iter(|| {
+            feed!(
+                NmeaParser::<256>::default(),
+                black_box(&stream),
+                |p: &mut NmeaParser<256>| {
+                    let mut n = 0;
+                    while let Some(line) = p.next_ref() {
+                        black_box(line);
+                        n += 1;
+                    }
+                    n
+                }
+            )
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("parser_nearly_full");
+    group.throughput(Throughput::Bytes(long.len() as u64));
+    group.bench_function("legacy", |b| {
+        b.iter(|| {
+            feed!(
+                legacy::Parser::<NEARLY_FULL>::default(),
+                black_box(&long),
+                |p: &mut legacy::Parser<NEARLY_FULL>| p.map(black_box).count()
+            )
+        })
+    });
+    group.bench_function("ring", |b| {
+        b.iter(|| {
+            feed!(
+                NmeaParser::<NEARLY_FULL>::default(),
+                black_box(&long),
+                |p: &mut NmeaParser<NEARLY_FULL>| p.map(black_box).count()
+            )
+        })
+    });
+    group.finish();
+
+    // 两种实现结果一致
+    let mut a = legacy::Parser::<256>::default();
+    let mut b = NmeaParser::<256>::default();
+    for chunk in stream.chunks(CHUNK) {
+        a.as_buf()[..chunk.len()].copy_from_slice(chunk);
+        a.notify_received(chunk.len());
+        b.as_buf()[..chunk.len()].copy_from_slice(chunk);
+        b.notify_received(chunk.len());
+        assert!(a.by_ref().eq(b.by_ref()));
+    }
+}
+
+criterion_group!(benches, bench_parser);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nmea::{NmeaLine, NmeaLineRef, ParseMode};
+
+// 去掉 `$` 和校验和的语句体，解析后重新编码
+fuzz_target!(|body: &str| {
+    if let Ok(line) = NmeaLineRef::parse(body) {
+        let _ = line.to_string();
+    }
+    if let Ok(line) = body.parse::<NmeaLine>() {
+        let _ = line.to_string();
+    }
+    if let Ok(line) = NmeaLine::parse_with(body, ParseMode::Lenient) {
+        let _ = line.to_string();
+    }
+});
@@ -0,0 +1,56 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nmea::{ChecksumPolicy, ChecksumRules, Framing, NmeaParser, NmeaVecParser};
+
+// 首字节选择分块大小和解析器配置，其余字节作为串口数据分块送入
+fuzz_target!(|data: &[u8]| {
+    let Some((&config, stream)) = data.split_first() else {
+        return;
+    };
+    let chunk = (config & 0x3f) as usize + 1;
+    let framing = if config & 0x40 != 0 {
+        Framing::strict()
+    } else {
+        Framing::Lenient
+    };
+    let rules = if config & 0x80 != 0 {
+        ChecksumRules::new(ChecksumPolicy::Ignored)
+    } else {
+        ChecksumRules::default()
+    };
+
+    let mut parser = NmeaParser::<128>::default()
+        .with_framing(framing)
+        .with_checksum(rules.clone());
+    let mut vec_parser = NmeaVecParser::new(64, 256)
+        .with_framing(framing)
+        .with_checksum(rules);
+    for chunk in stream.chunks(chunk) {
+        let mut rest = chunk;
+        while !rest.is_empty() {
+            for sentence in parser.diagnostics().flatten() {
+                let _ = sentence.line.to_string();
+            }
+            let buf = parser.as_buf();
+            let n = buf.len().min(rest.len());
+            buf[..n].copy_from_slice(&rest[..n]);
+            parser.notify_received(n);
+            rest = &rest[n..];
+        }
+        while let Some(sentence) = parser.next_ref() {
+            let _ = sentence.line.to_string();
+        }
+
+        let mut rest = chunk;
+        while !rest.is_empty() {
+            for _ in vec_parser.diagnostics() {}
+            let buf = vec_parser.as_buf();
+            let n = buf.len().min(rest.len());
+            buf[..n].copy_from_slice(&rest[..n]);
+            vec_parser.notify_received(n);
+            rest = &rest[n..];
+        }
+        while vec_parser.next_ref().is_some() {}
+    }
+});
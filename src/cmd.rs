@@ -1,39 +1,78 @@
-﻿use super::BodyParseError;
-use std::str::FromStr;
+﻿use super::{text, BodyParseError, Text};
+use core::{fmt, str::FromStr};
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Body {
-    Get(String),
-    Set(String),
-    Undefined(String, String),
+    Get(Text),
+    Set(Text),
+    Undefined(Text, Text),
 }
 
-impl FromStr for Body {
-    type Err = BodyParseError;
+/// 借用原文的 [`Body`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodyRef<'a> {
+    Get(&'a str),
+    Set(&'a str),
+    Undefined(&'a str, &'a str),
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl<'a> BodyRef<'a> {
+    pub fn parse(s: &'a str) -> Result<Self, BodyParseError> {
         match s.split_once(',') {
             Some((head, tail)) => match head {
-                "get" => Ok(Body::Get(tail.into())),
-                "set" => Ok(Body::Set(tail.into())),
-                unknown => Ok(Body::Undefined(unknown.into(), tail.into())),
+                "get" => Ok(Self::Get(tail)),
+                "set" => Ok(Self::Set(tail)),
+                unknown => Ok(Self::Undefined(unknown, tail)),
             },
             None => Err(BodyParseError::MissingField("CMD:Type")),
         }
     }
+
+    /// 复制为 [`Body`]，没有 `std` 时文本可能超过容量
+    pub fn try_to_owned(&self) -> Result<Body, BodyParseError> {
+        Ok(match *self {
+            Self::Get(body) => Body::Get(text(body, "CMD:Body")?),
+            Self::Set(body) => Body::Set(text(body, "CMD:Body")?),
+            Self::Undefined(head, body) => {
+                Body::Undefined(text(head, "CMD:Type")?, text(body, "CMD:Body")?)
+            }
+        })
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_owned(&self) -> Body {
+        self.try_to_owned().expect("String has no capacity limit")
+    }
 }
 
-impl ToString for Body {
-    fn to_string(&self) -> String {
-        match self {
-            Self::Get(body) => fmt_line("get", body),
-            Self::Set(body) => fmt_line("set", body),
-            Self::Undefined(head, body) => fmt_line(head, body),
-        }
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BodyRef::parse(s)?.try_to_owned()
     }
 }
 
-#[inline]
-fn fmt_line(head: &str, body: &str) -> String {
-    format!("$cmd,{},{}*ff\r\n", head, body)
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let borrowed = match self {
+            Self::Get(body) => BodyRef::Get(body),
+            Self::Set(body) => BodyRef::Set(body),
+            Self::Undefined(head, body) => BodyRef::Undefined(head, body),
+        };
+        borrowed.fmt(f)
+    }
+}
+
+impl fmt::Display for BodyRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (head, body) = match *self {
+            Self::Get(body) => ("get", body),
+            Self::Set(body) => ("set", body),
+            Self::Undefined(head, body) => (head, body),
+        };
+        write!(f, "$cmd,{},{}*ff\r\n", head, body)
+    }
 }
@@ -0,0 +1,205 @@
+﻿use super::{
+    cmd,
+    parser::{ChecksumRules, Cursors, Framing},
+    NmeaLine, ParseMode, SentenceRegistry,
+};
+use bytes::{Buf, BytesMut};
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// NMEA 语句的 [`Decoder`]/[`Encoder`]，用于 [`Framed`](tokio_util::codec::Framed) 等异步适配器
+///
+/// 分帧和解码与 [`NmeaParser`](super::NmeaParser) 的迭代器共用同一套规则：校验失败或无法解析的语句被跳过。
+/// 超过 `max_length` 仍未结束的语句会被丢弃，以免缓冲区无限增长。
+#[derive(Clone, Debug)]
+pub struct NmeaCodec {
+    max_length: usize,
+    cursor_r: Cursors,
+}
+
+impl Default for NmeaCodec {
+    #[inline]
+    fn default() -> Self {
+        Self::with_max_length(256)
+    }
+}
+
+impl NmeaCodec {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置单条语句的最大长度，包括 `$` 和校验和
+    #[inline]
+    pub fn with_max_length(max_length: usize) -> Self {
+        Self {
+            max_length,
+            cursor_r: Cursors::default(),
+        }
+    }
+
+    /// 设置分帧规则，默认为 [`Framing::Lenient`]；[`Framing::Strict`] 的 `max_len` 比 `max_length` 大时不起作用
+    #[inline]
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.cursor_r.framing = framing;
+        self
+    }
+
+    /// 设置校验和规则，默认见 [`ChecksumRules::default`]
+    #[inline]
+    pub fn with_checksum(mut self, rules: ChecksumRules) -> Self {
+        self.cursor_r.checksum = rules;
+        self
+    }
+
+    /// 设置语句体的解析模式，默认为 [`ParseMode::Strict`]
+    #[inline]
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.cursor_r.mode = mode;
+        self
+    }
+
+    /// 设置注册的语句，默认为空
+    #[inline]
+    pub fn with_registry(mut self, registry: SentenceRegistry) -> Self {
+        self.cursor_r.registry = registry;
+        self
+    }
+
+    #[inline]
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+}
+
+impl Decoder for NmeaCodec {
+    type Item = NmeaLine;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let line = loop {
+            let Some(sentence) = self.cursor_r.next_ref(src, false, self.max_length) else {
+                break None;
+            };
+            if let Ok(line) = sentence.line.try_into_owned() {
+                break Some(line);
+            }
+        };
+        // 已解析的字节移出缓冲区
+        src.advance(self.cursor_r.r);
+        self.cursor_r.reset();
+        if line.is_none() {
+            src.reserve(self.max_length.saturating_sub(src.len()));
+        }
+        Ok(line)
+    }
+
+    /// 流结束时不完整的语句直接丢弃
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let line = self.decode(src)?;
+        if line.is_none() {
+            self.cursor_r.r = src.len();
+            self.cursor_r.reset();
+            src.clear();
+        }
+        Ok(line)
+    }
+}
+
+impl Encoder<NmeaLine> for NmeaCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: NmeaLine, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(&item, dst)
+    }
+}
+
+impl Encoder<&NmeaLine> for NmeaCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: &NmeaLine, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(item.to_string().as_bytes());
+        Ok(())
+    }
+}
+
+impl Encoder<cmd::Body> for NmeaCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, item: cmd::Body, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(item.to_string().as_bytes());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_codec() {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::{Framed, FramedRead};
+
+    const STREAM: &[u8] =
+        b"xx$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n\
+$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4D\r\n\
+$GPGGA,021604.00,3015.5086891,N,12007.8373128,E,1,12,0.9,10.9,M,7.1,M,,*65\r\n\
+$GTIMU,0,6.0";
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let (a, b) = tokio::io::duplex(64);
+        let mut input = FramedRead::new(b, NmeaCodec::new());
+        let mut writer = a;
+        tokio::spawn(async move {
+            for chunk in STREAM.chunks(5) {
+                writer.write_all(chunk).await.unwrap();
+            }
+        });
+        let mut lines = Vec::new();
+        while let Some(line) = input.next().await {
+            lines.push(line.unwrap());
+        }
+        assert_eq!(lines.len(), 2);
+        assert!(matches!(lines[0], NmeaLine::GTIMU(_)));
+        assert!(matches!(lines[1], NmeaLine::GGA(..)));
+
+        // 编码的语句可以原样解码回来
+        let (a, b) = tokio::io::duplex(256);
+        let mut a = Framed::new(a, NmeaCodec::new());
+        let mut b = Framed::new(b, NmeaCodec::new());
+        for line in lines {
+            a.send(line).await.unwrap();
+        }
+        assert!(matches!(b.next().await, Some(Ok(NmeaLine::GTIMU(_)))));
+        assert!(matches!(b.next().await, Some(Ok(NmeaLine::GGA(..)))));
+    });
+
+    // 行内出现新的 '$'：宽松分帧把两条语句当作一条，校验失败后都丢弃；严格分帧只丢弃被截断的前半条
+    const CORRUPTED: &[u8] = b"$GPGGA,021604.00,3015.50\
+$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n";
+    fn decode_all(mut codec: NmeaCodec, stream: &[u8]) -> Vec<NmeaLine> {
+        let mut lines = Vec::new();
+        let mut src = BytesMut::new();
+        for chunk in stream.chunks(5) {
+            src.extend_from_slice(chunk);
+            while let Some(line) = codec.decode(&mut src).unwrap() {
+                lines.push(line);
+            }
+        }
+        while let Some(line) = codec.decode_eof(&mut src).unwrap() {
+            lines.push(line);
+        }
+        lines
+    }
+    assert!(decode_all(NmeaCodec::new(), CORRUPTED).is_empty());
+    let lines = decode_all(NmeaCodec::new().with_framing(Framing::strict()), CORRUPTED);
+    assert!(matches!(lines[..], [NmeaLine::GTIMU(_)]));
+    // 严格分帧的最大长度
+    let strict = NmeaCodec::new().with_framing(Framing::Strict { max_len: 64 });
+    assert!(decode_all(strict, CORRUPTED).is_empty());
+}
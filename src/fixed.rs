@@ -0,0 +1,277 @@
+﻿use core::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// 十进制定点小数
+///
+/// 解析时保留小数位数、整数部分的位数和负号，因此可以原样格式化回文本。
+/// 比较和运算按数值进行，`1.50 == 1.5`。
+#[derive(Clone, Copy, Debug)]
+pub struct Fixed {
+    value: i128,    // 去掉小数点后的整数
+    scale: u8,      // 小数位数
+    width: u8,      // 整数部分的位数
+    negative: bool, // 文本带负号，用于保留 `-0.0`
+}
+
+impl Fixed {
+    /// 用 `value` × 10^-`scale` 构造
+    #[inline]
+    pub const fn new(value: i128, scale: u8) -> Self {
+        Self {
+            value,
+            scale,
+            width: 1,
+            negative: value < 0,
+        }
+    }
+
+    /// 去掉小数点后的整数
+    #[inline]
+    pub const fn value(&self) -> i128 {
+        self.value
+    }
+
+    /// 小数位数
+    #[inline]
+    pub const fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// 格式化时整数部分至少补零到 `width` 位
+    #[inline]
+    pub fn with_min_width(mut self, width: u8) -> Self {
+        self.width = self.width.max(width);
+        self
+    }
+
+    /// 调整到 `scale` 位小数，多余的位数直接截断，溢出时返回 [`None`]
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        let value = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.value,
+            Ordering::Greater => self.value.checked_mul(pow10(scale - self.scale)?)?,
+            Ordering::Less => self.value / pow10(self.scale - scale)?,
+        };
+        Some(Self {
+            value,
+            scale,
+            ..*self
+        })
+    }
+
+    /// 转换为浮点数
+    #[inline]
+    pub fn to_f64(&self) -> f64 {
+        // 10^n 在 n <= 22 时是精确的，与 powi 结果相同，且不依赖 std
+        let divisor = (0..self.scale).fold(1f64, |d, _| d * 10.0);
+        self.value as f64 / divisor
+    }
+
+    /// 对齐小数位数，溢出时 panic，与整数运算相同
+    fn align(a: Self, b: Self) -> (i128, i128, u8) {
+        let scale = a.scale.max(b.scale);
+        (
+            a.value * pow10(scale - a.scale).unwrap(),
+            b.value * pow10(scale - b.scale).unwrap(),
+            scale,
+        )
+    }
+}
+
+#[inline]
+fn pow10(n: u8) -> Option<i128> {
+    10i128.checked_pow(n as u32)
+}
+
+/// 比较 `a` × 10^`n` 与 `b`，不会溢出
+fn cmp_scaled(a: i128, n: u8, b: i128) -> Ordering {
+    match pow10(n) {
+        Some(p) => {
+            let r = b.rem_euclid(p);
+            a.cmp(&b.div_euclid(p)).then(if r > 0 {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            })
+        }
+        // |b| < 10^39，a 不为 0 时 a × 10^n 的绝对值更大
+        None if a != 0 => a.cmp(&0),
+        None => 0.cmp(&b),
+    }
+}
+
+impl FromStr for Fixed {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(());
+        }
+        let mut value = 0i128;
+        for b in int.bytes().chain(frac.bytes()) {
+            if !b.is_ascii_digit() {
+                return Err(());
+            }
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add((b - b'0') as i128))
+                .ok_or(())?;
+        }
+        Ok(Self {
+            value: if negative { -value } else { value },
+            scale: frac.len().try_into().map_err(|_| ())?,
+            width: int.len().try_into().map_err(|_| ())?,
+            negative,
+        })
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative || self.value < 0 {
+            f.write_str("-")?;
+        }
+        let value = self.value.unsigned_abs();
+        // 10^scale 超出 u128 时整数部分必为 0
+        let (int, frac) = match 10u128.checked_pow(self.scale as u32) {
+            Some(p) => (value / p, value % p),
+            None => (0, value),
+        };
+        if int != 0 || self.width > 0 {
+            write!(f, "{:0w$}", int, w = self.width as usize)?;
+        }
+        if self.scale > 0 {
+            write!(f, ".{:0n$}", frac, n = self.scale as usize)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for Fixed {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Fixed {}
+
+impl PartialOrd for Fixed {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fixed {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.scale <= other.scale {
+            cmp_scaled(self.value, other.scale - self.scale, other.value)
+        } else {
+            cmp_scaled(other.value, self.scale - other.scale, self.value).reverse()
+        }
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let (a, b, scale) = Self::align(self, rhs);
+        Self::new(a + b, scale)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        let (a, b, scale) = Self::align(self, rhs);
+        Self::new(a - b, scale)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.value * rhs.value, self.scale + rhs.scale)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            value: -self.value,
+            negative: !self.negative,
+            ..self
+        }
+    }
+}
+
+#[test]
+fn test_fixed() {
+    for s in [
+        "3959.55874779",
+        "-0.000",
+        "0959.50",
+        "-9.2862",
+        "60",
+        ".5",
+        "123456789012345678901234567.89",
+    ] {
+        assert_eq!(s.parse::<Fixed>().unwrap().to_string(), s);
+    }
+    for s in [
+        "",
+        "-",
+        ".",
+        "1.2.3",
+        "1e3",
+        "999999999999999999999999999999999999999999",
+    ] {
+        assert!(s.parse::<Fixed>().is_err());
+    }
+
+    let a = "1.50".parse::<Fixed>().unwrap();
+    let b = "-0.25".parse::<Fixed>().unwrap();
+    assert_eq!(a, Fixed::new(15, 1));
+    assert!(b < a);
+    assert_eq!((a + b).to_string(), "1.25");
+    assert_eq!((a - b).to_string(), "1.75");
+    assert_eq!((a * b).to_string(), "-0.3750");
+    assert_eq!((-b).to_string(), "0.25");
+    assert_eq!(a.rescale(1).unwrap().to_string(), "1.5");
+    assert_eq!(b.to_f64(), -0.25);
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    /// 任意长度的小数都能原样格式化，比较不会溢出
+    #[test]
+    fn prop_fixed_never_panics(
+        a in "[-+]?[0-9]{0,48}(\\.[0-9]{1,48})?",
+        b in "[-+]?[0-9]{0,48}(\\.[0-9]{1,48})?",
+    ) {
+        if let (Ok(x), Ok(y)) = (a.parse::<Fixed>(), b.parse::<Fixed>()) {
+            proptest::prop_assert_eq!(x.to_string(), a.trim_start_matches('+'));
+            proptest::prop_assert_eq!(x.cmp(&y), y.cmp(&x).reverse());
+            // 能对齐时与整数比较的结果相同
+            let scale = x.scale().max(y.scale());
+            if let (Some(x1), Some(y1)) = (x.rescale(scale), y.rescale(scale)) {
+                proptest::prop_assert_eq!(x.cmp(&y), x1.value().cmp(&y1.value()));
+            }
+        }
+    }
+}
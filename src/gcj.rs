@@ -0,0 +1,167 @@
+﻿use super::Lla;
+use std::f64::consts::PI;
+
+/// GCJ-02 使用的 Krasovsky 1940 椭球长半轴 / m
+const A: f64 = 6378245.0;
+/// Krasovsky 1940 椭球第一偏心率的平方
+const EE: f64 = 0.006_693_421_622_965_943;
+const X_PI: f64 = PI * 3000.0 / 180.0;
+
+/// 国内地图坐标转换，高度不变
+///
+/// 坐标在国境矩形范围外时，WGS84 与 GCJ-02 之间不做偏移，这与高德等地图服务的行为一致。
+impl Lla {
+    /// WGS84 转换为 GCJ-02（高德、腾讯地图）
+    pub fn wgs84_to_gcj02(&self) -> Lla {
+        if out_of_china(self) {
+            return *self;
+        }
+        let (d_lat, d_lon) = delta(self.latitude, self.longitude);
+        Lla {
+            latitude: self.latitude + d_lat,
+            longitude: self.longitude + d_lon,
+            altitude: self.altitude,
+        }
+    }
+
+    /// GCJ-02 转换为 WGS84，迭代到误差小于 10^-10°
+    pub fn gcj02_to_wgs84(&self) -> Lla {
+        if out_of_china(self) {
+            return *self;
+        }
+        let mut wgs = *self;
+        for _ in 0..30 {
+            let gcj = wgs.wgs84_to_gcj02();
+            let d_lat = self.latitude - gcj.latitude;
+            let d_lon = self.longitude - gcj.longitude;
+            wgs.latitude += d_lat;
+            wgs.longitude += d_lon;
+            if d_lat.abs() < 1e-10 && d_lon.abs() < 1e-10 {
+                break;
+            }
+        }
+        wgs
+    }
+
+    /// GCJ-02 转换为 BD-09（百度地图）
+    pub fn gcj02_to_bd09(&self) -> Lla {
+        let (x, y) = (self.longitude, self.latitude);
+        let z = x.hypot(y) + 0.00002 * (y * X_PI).sin();
+        let theta = y.atan2(x) + 0.000003 * (x * X_PI).cos();
+        Lla {
+            latitude: z * theta.sin() + 0.006,
+            longitude: z * theta.cos() + 0.0065,
+            altitude: self.altitude,
+        }
+    }
+
+    /// BD-09 转换为 GCJ-02，从近似公式的结果开始迭代到误差小于 10^-10°
+    pub fn bd09_to_gcj02(&self) -> Lla {
+        let (x, y) = (self.longitude - 0.0065, self.latitude - 0.006);
+        let z = x.hypot(y) - 0.00002 * (y * X_PI).sin();
+        let theta = y.atan2(x) - 0.000003 * (x * X_PI).cos();
+        let mut gcj = Lla {
+            latitude: z * theta.sin(),
+            longitude: z * theta.cos(),
+            altitude: self.altitude,
+        };
+        for _ in 0..30 {
+            let bd = gcj.gcj02_to_bd09();
+            let d_lat = self.latitude - bd.latitude;
+            let d_lon = self.longitude - bd.longitude;
+            gcj.latitude += d_lat;
+            gcj.longitude += d_lon;
+            if d_lat.abs() < 1e-10 && d_lon.abs() < 1e-10 {
+                break;
+            }
+        }
+        gcj
+    }
+
+    /// WGS84 转换为 BD-09
+    #[inline]
+    pub fn wgs84_to_bd09(&self) -> Lla {
+        self.wgs84_to_gcj02().gcj02_to_bd09()
+    }
+
+    /// BD-09 转换为 WGS84
+    #[inline]
+    pub fn bd09_to_wgs84(&self) -> Lla {
+        self.bd09_to_gcj02().gcj02_to_wgs84()
+    }
+}
+
+#[inline]
+fn out_of_china(lla: &Lla) -> bool {
+    !(72.004..=137.8347).contains(&lla.longitude) || !(0.8293..=55.8271).contains(&lla.latitude)
+}
+
+/// GCJ-02 相对 WGS84 的偏移 / °
+fn delta(lat: f64, lon: f64) -> (f64, f64) {
+    let (x, y) = (lon - 105.0, lat - 35.0);
+    let common =
+        0.1 * x * y + (20.0 * (6.0 * x * PI).sin() + 20.0 * (2.0 * x * PI).sin()) * 2.0 / 3.0;
+    let d_lat = -100.0
+        + 2.0 * x
+        + 3.0 * y
+        + 0.2 * y * y
+        + 0.2 * x.abs().sqrt()
+        + common
+        + (20.0 * (y * PI).sin() + 40.0 * (y / 3.0 * PI).sin()) * 2.0 / 3.0
+        + (160.0 * (y / 12.0 * PI).sin() + 320.0 * (y / 30.0 * PI).sin()) * 2.0 / 3.0;
+    let d_lon = 300.0
+        + x
+        + 2.0 * y
+        + 0.1 * x * x
+        + 0.1 * x.abs().sqrt()
+        + common
+        + (20.0 * (x * PI).sin() + 40.0 * (x / 3.0 * PI).sin()) * 2.0 / 3.0
+        + (150.0 * (x / 12.0 * PI).sin() + 300.0 * (x / 30.0 * PI).sin()) * 2.0 / 3.0;
+
+    let rad_lat = lat.to_radians();
+    let magic = 1.0 - EE * rad_lat.sin().powi(2);
+    let sqrt_magic = magic.sqrt();
+    (
+        d_lat * 180.0 / (A * (1.0 - EE) / (magic * sqrt_magic) * PI),
+        d_lon * 180.0 / (A / sqrt_magic * rad_lat.cos() * PI),
+    )
+}
+
+#[test]
+fn test_gcj02_bd09() {
+    // 参考值来自 coordtransform 的文档
+    let p = Lla {
+        latitude: 39.915,
+        longitude: 116.404,
+        altitude: 10.0,
+    };
+    let gcj = p.wgs84_to_gcj02();
+    assert!((gcj.latitude - 39.91640428150164).abs() < 1e-12);
+    assert!((gcj.longitude - 116.41024449916938).abs() < 1e-12);
+    assert_eq!(gcj.altitude, 10.0);
+    let bd = p.gcj02_to_bd09();
+    assert!((bd.latitude - 39.92133699351022).abs() < 1e-12);
+    assert!((bd.longitude - 116.41036949371029).abs() < 1e-12);
+    // 迭代反算，与近似公式的结果相差不到 1 m
+    let gcj = p.bd09_to_gcj02();
+    assert!((gcj.latitude - 39.90865673957631).abs() < 1e-5);
+    assert!((gcj.longitude - 116.39762729119315).abs() < 1e-5);
+    let bd = gcj.gcj02_to_bd09();
+    assert!((bd.latitude - p.latitude).abs() < 1e-10);
+    assert!((bd.longitude - p.longitude).abs() < 1e-10);
+    let wgs = p.wgs84_to_bd09().bd09_to_wgs84();
+    assert!((wgs.latitude - p.latitude).abs() < 1e-10);
+    assert!((wgs.longitude - p.longitude).abs() < 1e-10);
+    let wgs = p.wgs84_to_gcj02().gcj02_to_wgs84();
+    assert!((wgs.latitude - p.latitude).abs() < 1e-10);
+    assert!((wgs.longitude - p.longitude).abs() < 1e-10);
+
+    // 境外不偏移
+    let tokyo = Lla {
+        latitude: 35.6812,
+        longitude: 139.7671,
+        altitude: 0.0,
+    };
+    assert_eq!(tokyo.wgs84_to_gcj02(), tokyo);
+    assert_eq!(tokyo.gcj02_to_wgs84(), tokyo);
+}
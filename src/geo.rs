@@ -0,0 +1,363 @@
+﻿use super::Fixed;
+
+/// WGS84 大地坐标
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lla {
+    pub latitude: f64,  // 纬度 / °，北正南负
+    pub longitude: f64, // 经度 / °，东正西负
+    pub altitude: f64,  // 椭球高 / m
+}
+
+impl Lla {
+    /// 纬度 / rad
+    #[inline]
+    pub fn latitude_rad(&self) -> f64 {
+        self.latitude.to_radians()
+    }
+
+    /// 经度 / rad
+    #[inline]
+    pub fn longitude_rad(&self) -> f64 {
+        self.longitude.to_radians()
+    }
+}
+
+#[cfg(feature = "std")]
+/// WGS84 地心地固坐标 / m
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ecef {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// 东北天坐标或速度
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Enu {
+    pub e: f64,
+    pub n: f64,
+    pub u: f64,
+}
+
+/// WGS84 长半轴 / m
+pub const WGS84_A: f64 = 6378137.0;
+/// WGS84 扁率
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+#[cfg(feature = "std")]
+/// WGS84 第一偏心率的平方
+const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+
+#[cfg(feature = "std")]
+impl Lla {
+    pub fn to_ecef(&self) -> Ecef {
+        let (sin_lat, cos_lat) = self.latitude_rad().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude_rad().sin_cos();
+        let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+        Ecef {
+            x: (n + self.altitude) * cos_lat * cos_lon,
+            y: (n + self.altitude) * cos_lat * sin_lon,
+            z: (n * (1.0 - WGS84_E2) + self.altitude) * sin_lat,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Ecef {
+    pub fn to_lla(&self) -> Lla {
+        let p = self.x.hypot(self.y);
+        // 迭代求纬度，两极附近也收敛
+        let mut lat = self.z.atan2(p * (1.0 - WGS84_E2));
+        for _ in 0..6 {
+            let sin_lat = lat.sin();
+            let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+            lat = (self.z + WGS84_E2 * n * sin_lat).atan2(p);
+        }
+        let (sin_lat, cos_lat) = lat.sin_cos();
+        Lla {
+            latitude: lat.to_degrees(),
+            longitude: self.y.atan2(self.x).to_degrees(),
+            altitude: p * cos_lat + self.z * sin_lat
+                - WGS84_A * (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+/// 以某一点为原点的东北天局部坐标系
+#[derive(Clone, Debug)]
+pub struct EnuFrame {
+    origin: Lla,
+    origin_ecef: Ecef,
+    rotation: Rotation,
+}
+
+#[cfg(feature = "std")]
+/// 原点处 ECEF 到 ENU 的旋转
+#[derive(Clone, Copy, Debug)]
+struct Rotation {
+    sin_lat: f64,
+    cos_lat: f64,
+    sin_lon: f64,
+    cos_lon: f64,
+}
+
+#[cfg(feature = "std")]
+impl Rotation {
+    fn at(lla: &Lla) -> Self {
+        let (sin_lat, cos_lat) = lla.latitude_rad().sin_cos();
+        let (sin_lon, cos_lon) = lla.longitude_rad().sin_cos();
+        Self {
+            sin_lat,
+            cos_lat,
+            sin_lon,
+            cos_lon,
+        }
+    }
+
+    fn to_enu(self, [x, y, z]: [f64; 3]) -> Enu {
+        let Self {
+            sin_lat,
+            cos_lat,
+            sin_lon,
+            cos_lon,
+        } = self;
+        Enu {
+            e: -sin_lon * x + cos_lon * y,
+            n: -sin_lat * cos_lon * x - sin_lat * sin_lon * y + cos_lat * z,
+            u: cos_lat * cos_lon * x + cos_lat * sin_lon * y + sin_lat * z,
+        }
+    }
+
+    fn to_ecef(self, Enu { e, n, u }: Enu) -> [f64; 3] {
+        let Self {
+            sin_lat,
+            cos_lat,
+            sin_lon,
+            cos_lon,
+        } = self;
+        [
+            -sin_lon * e - sin_lat * cos_lon * n + cos_lat * cos_lon * u,
+            cos_lon * e - sin_lat * sin_lon * n + cos_lat * sin_lon * u,
+            cos_lat * n + sin_lat * u,
+        ]
+    }
+}
+
+#[cfg(feature = "std")]
+impl EnuFrame {
+    pub fn new(origin: Lla) -> Self {
+        Self {
+            origin,
+            origin_ecef: origin.to_ecef(),
+            rotation: Rotation::at(&origin),
+        }
+    }
+
+    /// 以语句中的位置为原点，没有有效定位时返回 [`None`]
+    #[inline]
+    pub fn from_position(fix: &impl Position) -> Option<Self> {
+        fix.lla().map(Self::new)
+    }
+
+    #[inline]
+    pub fn origin(&self) -> Lla {
+        self.origin
+    }
+
+    /// 大地坐标转换为局部坐标
+    pub fn to_enu(&self, lla: &Lla) -> Enu {
+        let Ecef { x, y, z } = lla.to_ecef();
+        let o = self.origin_ecef;
+        self.rotation.to_enu([x - o.x, y - o.y, z - o.z])
+    }
+
+    /// 局部坐标转换为大地坐标
+    pub fn to_lla(&self, enu: Enu) -> Lla {
+        let [x, y, z] = self.rotation.to_ecef(enu);
+        let o = self.origin_ecef;
+        Ecef {
+            x: x + o.x,
+            y: y + o.y,
+            z: z + o.z,
+        }
+        .to_lla()
+    }
+
+    /// 语句中的位置在局部坐标系中的坐标
+    #[inline]
+    pub fn position(&self, fix: &impl Position) -> Option<Enu> {
+        Some(self.to_enu(&fix.lla()?))
+    }
+
+    /// 语句中的东北天速度旋转到局部坐标系
+    pub fn velocity(&self, fix: &impl Position) -> Option<Enu> {
+        let velocity = fix.velocity()?;
+        let ecef = Rotation::at(&fix.lla()?).to_ecef(velocity);
+        Some(self.rotation.to_enu(ecef))
+    }
+}
+
+/// 携带定位结果的语句
+pub trait Position {
+    /// 语句中的位置，没有有效定位时返回 [`None`]
+    fn lla(&self) -> Option<Lla>;
+
+    /// 语句中的东北天速度 / m/s，不带速度的语句返回 [`None`]
+    #[inline]
+    fn velocity(&self) -> Option<Enu> {
+        None
+    }
+}
+
+/// 将 `(d)ddmm.mmmm` 格式的度分转换为度，`negative` 表示南纬或西经
+pub fn ddmm_to_degrees(ddmm: Fixed, negative: bool) -> f64 {
+    // 用整数拆分度和分，避免浮点误差；100 × 10^scale 溢出时度数必为 0
+    let value = ddmm.value().unsigned_abs();
+    let (degrees, minutes) = match 10u128
+        .checked_pow(ddmm.scale() as u32)
+        .and_then(|p| p.checked_mul(100))
+    {
+        Some(p) => (value / p, value % p),
+        None => (0, value),
+    };
+    let divisor = (0..ddmm.scale()).fold(60f64, |d, _| d * 10.0);
+    let degrees = degrees as f64 + minutes as f64 / divisor;
+    if negative {
+        -degrees
+    } else {
+        degrees
+    }
+}
+
+#[test]
+fn test_position() {
+    use crate::{gpfpd, gpgga};
+
+    let gga = "060220.00,3959.55874779,N,11619.61828897,W,1,17,1.6,60.1397,M,-9.2862,M,,"
+        .parse::<gpgga::Body>()
+        .unwrap()
+        .lla()
+        .unwrap();
+    assert!((gga.latitude - 39.992645796).abs() < 1e-9);
+    assert!((gga.longitude + 116.326971483).abs() < 1e-9);
+    assert!((gga.altitude - 50.8535).abs() < 1e-9);
+
+    // 小数位数很多的度分不会溢出
+    let tiny = "0.00000000000000000000000000000000000006".parse().unwrap();
+    assert!((ddmm_to_degrees(tiny, true) + 1e-39).abs() < 1e-50);
+    assert!(ddmm_to_degrees(Fixed::new(i128::MIN, 0), false) > 1e36);
+
+    let fpd = "2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,-308580.94,0.003,-0.033,-3243.491,10.191,15,18,04"
+        .parse::<gpfpd::Body>()
+        .unwrap()
+        .lla()
+        .unwrap();
+    assert_eq!(fpd.latitude, 39.9926157);
+    assert_eq!(fpd.altitude, -308580.94);
+    assert!((fpd.latitude_rad() - 0.698003).abs() < 1e-6);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_enu() {
+    let origin = Lla {
+        latitude: 39.9926157,
+        longitude: 116.3269623,
+        altitude: 50.0,
+    };
+    let ecef = origin.to_ecef();
+    let lla = ecef.to_lla();
+    assert!((lla.latitude - origin.latitude).abs() < 1e-10);
+    assert!((lla.longitude - origin.longitude).abs() < 1e-10);
+    assert!((lla.altitude - origin.altitude).abs() < 1e-6);
+    let equator = Lla {
+        latitude: 0.0,
+        longitude: 0.0,
+        altitude: 0.0,
+    };
+    assert_eq!(
+        equator.to_ecef(),
+        Ecef {
+            x: WGS84_A,
+            y: 0.0,
+            z: 0.0
+        }
+    );
+
+    let frame = EnuFrame::new(origin);
+    let north = frame.to_enu(&Lla {
+        latitude: origin.latitude + 1e-5,
+        ..origin
+    });
+    assert!(north.e.abs() < 1e-6);
+    assert!((north.n - 1.1104).abs() < 1e-3);
+    let enu = Enu {
+        e: 100.0,
+        n: -50.0,
+        u: 3.0,
+    };
+    let back = frame.to_enu(&frame.to_lla(enu));
+    assert!(
+        (back.e - enu.e).abs() < 1e-6
+            && (back.n - enu.n).abs() < 1e-6
+            && (back.u - enu.u).abs() < 1e-6
+    );
+
+    // 原点处的速度不需要旋转
+    let fpd = "2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,50.00,0.003,-0.033,-3.491,10.191,15,18,04"
+        .parse::<crate::gpfpd::Body>()
+        .unwrap();
+    let frame = EnuFrame::from_position(&fpd).unwrap();
+    let v = frame.velocity(&fpd).unwrap();
+    assert!((v.e - 0.003).abs() < 1e-9 && (v.n + 0.033).abs() < 1e-9 && (v.u + 3.491).abs() < 1e-9);
+    assert!(frame.position(&fpd).unwrap().u.abs() < 1e-6);
+}
+
+#[cfg(test)]
+mod prop {
+    use crate::{gpchc, gpfpd, gpgga, gphpd, gprmc, Position};
+    use proptest::{collection::vec, option, prelude::*};
+
+    /// 带位置的语句，其中带小数点的字段会被替换为小数位数或整数位数很多的随机小数
+    const SAMPLES: [&str; 5] = [
+        "060220.00,3959.55874779,N,11619.61828897,W,1,17,1.6,60.1397,M,-9.2862,M,,",
+        "060220.00,A,3959.55874779,N,11619.61828897,E,0.012,,170621,,,D",
+        "2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,-308580.94,0.003,-0.033,-3243.491,10.191,15,18,04",
+        "0,0.000,0.000,0.000,0.000,0.0000000,0.0000000,0.00,0.000,0.000,0.000,0.000,0,0,00",
+        "2200,202733.70,258.15,-0.33,0.61,-0.03,0.03,-0.07,0.0058,-0.0111,1.0004,31.18826868,121.60418588,16.27,-0.011,-0.002,0.002,0.011,14,17,42,0,0002",
+    ];
+
+    proptest! {
+        /// 能解析的语句计算位置都不会 panic
+        #[test]
+        fn lla_never_panics(
+            sample in proptest::sample::select(SAMPLES.as_slice()),
+            fields in vec(option::of("[-+]?([0-9]{1,3}\\.[0-9]{0,38}|[0-9]{1,38}\\.[0-9]{0,2})"), 32),
+        ) {
+            let body = sample
+                .split(',')
+                .zip(&fields)
+                .map(|(field, random)| match random {
+                    Some(random) if field.contains('.') => random.as_str(),
+                    _ => field,
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            if let Ok(body) = body.parse::<gpgga::Body>() {
+                let _ = (body.lla(), body.velocity());
+            }
+            if let Ok(body) = body.parse::<gprmc::Body>() {
+                let _ = body.lat_lon();
+            }
+            if let Ok(body) = body.parse::<gpfpd::Body>() {
+                let _ = (body.lla(), body.velocity());
+            }
+            if let Ok(body) = body.parse::<gphpd::Body>() {
+                let _ = (body.lla(), body.velocity());
+            }
+            if let Ok(body) = body.parse::<gpchc::Body>() {
+                let _ = (body.lla(), body.velocity());
+            }
+        }
+    }
+}
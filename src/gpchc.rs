@@ -0,0 +1,214 @@
+﻿use super::{
+    extra, fixed, parse_code, time::GpsTimed, write_extra, BodyParseError, Code, Enu, Fixed, Lla,
+    ParseMode, Position, Text,
+};
+use core::{fmt, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    pub gps_week: u16,    // 自 1980-1-6 至当前的星期数（格林尼治时间）
+    pub gps_time: u32,    // 自本周日 0:00:00 至当前的 10 毫秒数（格林尼治时间）
+    pub heading: Fixed,   // 偏航角 [0   ,360) / °
+    pub pitch: Fixed,     // 俯仰角 [-90 ,90]  / °
+    pub roll: Fixed,      // 横滚角 [-180,180] / °
+    pub gyro_x: Fixed,    // X 轴角速度 / °/s
+    pub gyro_y: Fixed,    // Y 轴角速度 / °/s
+    pub gyro_z: Fixed,    // Z 轴角速度 / °/s
+    pub acc_x: Fixed,     // X 轴加速度 / g
+    pub acc_y: Fixed,     // Y 轴加速度 / g
+    pub acc_z: Fixed,     // Z 轴加速度 / g
+    pub latitude: Fixed,  // 纬度 [-90 ,90]  / °
+    pub longitude: Fixed, // 经度 [-180,180] / °
+    pub altitude: Fixed,  // 海拔 / m
+    pub vel_e: Fixed,     // 东向速度 / m/s
+    pub vel_n: Fixed,     // 北向速度 / m/s
+    pub vel_u: Fixed,     // 天向速度 / m/s
+    pub speed: Fixed,     // 车辆速度 / m/s
+    pub nsv1: u8,         // 主天线星数
+    pub nsv2: u8,         // 副天线星数
+    pub status: Status,   // 系统状态
+    pub age: u8,          // 差分延时 / s
+    pub warning: u16,     // 警告标志位
+    pub extra: Text,      // 宽松模式下保留的多余字段
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Status(pub SystemStatus, pub GnssStatus);
+
+code_enum! {
+    #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+    pub enum SystemStatus: crate::code_digit {
+        初始化 = 0x0,
+        卫导模式 = 0x1,
+        组合导航 = 0x2,
+        纯惯导 = 0x3,
+    }
+}
+
+code_enum! {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum GnssStatus: crate::code_digit {
+        不定位不定向 = 0x0,
+        单点定位定向 = 0x1,
+        伪距差分定位定向 = 0x2,
+        组合推算 = 0x3,
+        RTK固定解定位定向 = 0x4,
+        RTK浮点解定位定向 = 0x5,
+        单点定位不定向 = 0x6,
+        伪距差分定位不定向 = 0x7,
+        RTK固定解定位不定向 = 0x8,
+        RTK浮点解定位不定向 = 0x9,
+    }
+}
+
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
+        let mut s = s.split(',');
+        Ok(Self {
+            gps_week: parse_field!(s => "GPCHC:GPSWeek"),
+            gps_time: parse_field!(s => "GPCHC:GPSTime"; 2),
+            heading: parse_field!(s => "GPCHC:Heading"),
+            pitch: parse_field!(s => "GPCHC:Pitch"),
+            roll: parse_field!(s => "GPCHC:Roll"),
+            gyro_x: parse_field!(s => "GPCHC:GyroX"),
+            gyro_y: parse_field!(s => "GPCHC:GyroY"),
+            gyro_z: parse_field!(s => "GPCHC:GyroZ"),
+            acc_x: parse_field!(s => "GPCHC:AccX"),
+            acc_y: parse_field!(s => "GPCHC:AccY"),
+            acc_z: parse_field!(s => "GPCHC:AccZ"),
+            latitude: parse_field!(s => "GPCHC:Latitude"),
+            longitude: parse_field!(s => "GPCHC:Longitude"),
+            altitude: parse_field!(s => "GPCHC:Altitude"),
+            vel_e: parse_field!(s => "GPCHC:Ve"),
+            vel_n: parse_field!(s => "GPCHC:Vn"),
+            vel_u: parse_field!(s => "GPCHC:Vu"),
+            speed: parse_field!(s => "GPCHC:V"),
+            nsv1: parse_field!(s => "GPCHC:NSV1"),
+            nsv2: parse_field!(s => "GPCHC:NSV2"),
+            status: parse_field!(s => code "GPCHC:Status", mode),
+            age: parse_field!(s => "GPCHC:Age"),
+            warning: parse_field!(s => "GPCHC:Warning"),
+            extra: extra(all, 23, mode, "GPCHC:Extra")?,
+        })
+    }
+}
+
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl Code for Status {
+    /// 两位十进制数字，高位是卫导状态，低位是系统状态
+    fn read(s: &str) -> Option<Self> {
+        Some(Self(
+            SystemStatus::read(s.get(1..)?)?,
+            GnssStatus::read(s.get(..1)?)?,
+        ))
+    }
+
+    #[inline]
+    fn is_other(&self) -> bool {
+        self.0.is_other() || self.1.is_other()
+    }
+}
+
+impl FromStr for Status {
+    type Err = ();
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_code(s, ParseMode::Strict).ok_or(())
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{:04}",
+            self.gps_week,
+            fixed(self.gps_time, 2),
+            self.heading,
+            self.pitch,
+            self.roll,
+            self.gyro_x,
+            self.gyro_y,
+            self.gyro_z,
+            self.acc_x,
+            self.acc_y,
+            self.acc_z,
+            self.latitude,
+            self.longitude,
+            self.altitude,
+            self.vel_e,
+            self.vel_n,
+            self.vel_u,
+            self.speed,
+            self.nsv1,
+            self.nsv2,
+            self.status,
+            self.age,
+            self.warning,
+        )?;
+        write_extra(f, &self.extra)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.1.code(), self.0.code())
+    }
+}
+
+impl Position for Body {
+    fn lla(&self) -> Option<Lla> {
+        if self.status.0 == SystemStatus::初始化 {
+            return None;
+        }
+        Some(Lla {
+            latitude: self.latitude.to_f64(),
+            longitude: self.longitude.to_f64(),
+            altitude: self.altitude.to_f64(),
+        })
+    }
+
+    fn velocity(&self) -> Option<Enu> {
+        self.lla()?;
+        Some(Enu {
+            e: self.vel_e.to_f64(),
+            n: self.vel_n.to_f64(),
+            u: self.vel_u.to_f64(),
+        })
+    }
+}
+
+impl GpsTimed for Body {
+    #[inline]
+    fn gps_week_ms(&self) -> (u16, u32) {
+        (self.gps_week, self.gps_time * 10)
+    }
+}
+
+#[test]
+fn test_parse_gpchc() {
+    let body = "2200,202733.70,258.15,-0.33,0.61,-0.03,0.03,-0.07,0.0058,-0.0111,1.0004,31.18826868,121.60418588,16.27,-0.011,-0.002,0.002,0.011,14,17,42,0,0002"
+        .parse::<Body>()
+        .unwrap();
+    assert_eq!(body.gps_time, 20273370);
+    assert_eq!(body.heading, Fixed::new(25815, 2));
+    assert_eq!(body.acc_z, Fixed::new(10004, 4));
+    assert_eq!(body.latitude, Fixed::new(3118826868, 8));
+    assert_eq!(body.longitude, Fixed::new(12160418588, 8));
+    assert_eq!(body.vel_e, Fixed::new(-11, 3));
+    assert_eq!(
+        body.status,
+        Status(SystemStatus::组合导航, GnssStatus::RTK固定解定位定向)
+    );
+    assert_eq!(body.warning, 2);
+}
@@ -1,113 +1,176 @@
-﻿use super::BodyParseError;
-use std::str::FromStr;
+﻿use super::{
+    extra, fixed, parse_code, time::GpsTimed, write_extra, BodyParseError, Code, Enu, Fixed, Lla,
+    ParseMode, Position, Text,
+};
+use core::{fmt, str::FromStr};
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Body {
-    pub gps_week: u16,  // 自 1980-1-6 至当前的星期数（格林尼治时间）
-    pub gps_time: u32,  // 自本周日 0:00:00 至当前的毫秒数（格林尼治时间）
-    pub heading: u32,   // 偏航角 [0   ,360) / (10^-3)°
-    pub pitch: i32,     // 俯仰角 [-90 ,90]  / (10^-3)°
-    pub roll: i32,      // 横滚角 [-180,180] / (10^-3)°
-    pub latitude: i32,  // 纬度 [-90 ,90]  / (10^-7)°
-    pub longitude: i32, // 经度 [-180,180] / (10^-7)°
-    pub altitude: i32,  // 海拔 / (10^-2)m
-    pub vel_e: i32,     // 东向速度 / mm/s
-    pub vel_n: i32,     // 北向速度 / mm/s
-    pub vel_u: i32,     // 天向速度 / mm/s
-    pub baseline: u16,  // 基线长度 / mm
-    pub nsv1: u8,       // 天线 1 星数
-    pub nsv2: u8,       // 天线 2 星数
-    pub status: Status, // 系统状态
+    pub gps_week: u16,    // 自 1980-1-6 至当前的星期数（格林尼治时间）
+    pub gps_time: u32,    // 自本周日 0:00:00 至当前的毫秒数（格林尼治时间）
+    pub heading: Fixed,   // 偏航角 [0   ,360) / °
+    pub pitch: Fixed,     // 俯仰角 [-90 ,90]  / °
+    pub roll: Fixed,      // 横滚角 [-180,180] / °
+    pub latitude: Fixed,  // 纬度 [-90 ,90]  / °
+    pub longitude: Fixed, // 经度 [-180,180] / °
+    pub altitude: Fixed,  // 海拔 / m
+    pub vel_e: Fixed,     // 东向速度 / m/s
+    pub vel_n: Fixed,     // 北向速度 / m/s
+    pub vel_u: Fixed,     // 天向速度 / m/s
+    pub baseline: Fixed,  // 基线长度 / m
+    pub nsv1: u8,         // 天线 1 星数
+    pub nsv2: u8,         // 天线 2 星数
+    pub status: Status,   // 系统状态
+    pub extra: Text,      // 宽松模式下保留的多余字段
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Status(pub SystemStatus, pub RtkStatus);
 
-#[derive(Debug, PartialEq, PartialOrd)]
-pub enum SystemStatus {
-    初始化 = 0x0,
-    粗对准 = 0x1,
-    精对准 = 0x2,
-    GPS定位 = 0x3,
-    GPS定向 = 0x4,
-    RTK = 0x5,
-    DMI组合 = 0x6,
-    DMI标定 = 0x7,
-    纯惯性 = 0x8,
-    零速校正 = 0x9,
-    VG模式 = 0xA,
-    差分定向 = 0xB,
-    动态对准 = 0xC,
-    动态出错 = 0xF,
+code_enum! {
+    #[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+    pub enum SystemStatus: crate::code_hex {
+        初始化 = 0x0,
+        粗对准 = 0x1,
+        精对准 = 0x2,
+        GPS定位 = 0x3,
+        GPS定向 = 0x4,
+        RTK = 0x5,
+        DMI组合 = 0x6,
+        DMI标定 = 0x7,
+        纯惯性 = 0x8,
+        零速校正 = 0x9,
+        VG模式 = 0xA,
+        差分定向 = 0xB,
+        动态对准 = 0xC,
+        动态出错 = 0xF,
+    }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum RtkStatus {
-    Gps1Bd = 0,
-    双模 = 2,
-    RTK固定解 = 4,
-    RTK浮点解 = 5,
+code_enum! {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum RtkStatus: crate::code_hex {
+        Gps1Bd = 0,
+        双模 = 2,
+        RTK固定解 = 4,
+        RTK浮点解 = 5,
+    }
 }
 
-impl FromStr for Body {
-    type Err = BodyParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
         let mut s = s.split(',');
         Ok(Self {
             gps_week: parse_field!(s => "GPFPD:GPSWeek"),
             gps_time: parse_field!(s => "GPFPD:GPSTime"; 3),
-            heading: parse_field!(s => "GPFPD:Heading"; 3),
-            pitch: parse_field!(s => "GPFPD:Pitch"; 3),
-            roll: parse_field!(s => "GPFPD:Roll"; 3),
-            latitude: parse_field!(s => "GPFPD:Latitude"; 7),
-            longitude: parse_field!(s => "GPFPD:Longitude"; 7),
-            altitude: parse_field!(s => "GPFPD:Altitude"; 2),
-            vel_e: parse_field!(s => "GPFPD:Ve"; 3),
-            vel_n: parse_field!(s => "GPFPD:Vn"; 3),
-            vel_u: parse_field!(s => "GPFPD:Vu"; 3),
-            baseline: parse_field!(s => "GPFPD:Baseline"; 3),
+            heading: parse_field!(s => "GPFPD:Heading"),
+            pitch: parse_field!(s => "GPFPD:Pitch"),
+            roll: parse_field!(s => "GPFPD:Roll"),
+            latitude: parse_field!(s => "GPFPD:Latitude"),
+            longitude: parse_field!(s => "GPFPD:Longitude"),
+            altitude: parse_field!(s => "GPFPD:Altitude"),
+            vel_e: parse_field!(s => "GPFPD:Ve"),
+            vel_n: parse_field!(s => "GPFPD:Vn"),
+            vel_u: parse_field!(s => "GPFPD:Vu"),
+            baseline: parse_field!(s => "GPFPD:Baseline"),
             nsv1: parse_field!(s => "GPFPD:NSV1"),
             nsv2: parse_field!(s => "GPFPD:NSV2"),
-            status: parse_field!(s => "GPFPD:Status"),
+            status: parse_field!(s => code "GPFPD:Status", mode),
+            extra: extra(all, 15, mode, "GPFPD:Extra")?,
         })
     }
 }
 
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl Code for Status {
+    /// 两位十六进制数字，高位是 RTK 状态，低位是系统状态
+    fn read(s: &str) -> Option<Self> {
+        Some(Self(
+            SystemStatus::read(s.get(1..)?)?,
+            RtkStatus::read(s.get(..1)?)?,
+        ))
+    }
+
+    #[inline]
+    fn is_other(&self) -> bool {
+        self.0.is_other() || self.1.is_other()
+    }
+}
+
 impl FromStr for Status {
     type Err = ();
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use RtkStatus::*;
-        use SystemStatus::*;
-        match s.as_bytes() {
-            [a, b] => Ok(Self(
-                match b {
-                    b'0' => 初始化,
-                    b'1' => 粗对准,
-                    b'2' => 精对准,
-                    b'3' => GPS定位,
-                    b'4' => GPS定向,
-                    b'5' => RTK,
-                    b'6' => DMI组合,
-                    b'7' => DMI标定,
-                    b'8' => 纯惯性,
-                    b'9' => 零速校正,
-                    b'A' => VG模式,
-                    b'B' => 差分定向,
-                    b'C' => 动态对准,
-                    b'F' => 动态出错,
-                    _ => return Err(()),
-                },
-                match a {
-                    b'0' => Gps1Bd,
-                    b'2' => 双模,
-                    b'4' => RTK固定解,
-                    b'5' => RTK浮点解,
-                    _ => return Err(()),
-                },
-            )),
-            [..] => Err(()),
+        parse_code(s, ParseMode::Strict).ok_or(())
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.gps_week,
+            fixed(self.gps_time, 3),
+            self.heading,
+            self.pitch,
+            self.roll,
+            self.latitude,
+            self.longitude,
+            self.altitude,
+            self.vel_e,
+            self.vel_n,
+            self.vel_u,
+            self.baseline,
+            self.nsv1,
+            self.nsv2,
+            self.status,
+        )?;
+        write_extra(f, &self.extra)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}{:X}", self.1.code(), self.0.code())
+    }
+}
+
+impl Position for Body {
+    fn lla(&self) -> Option<Lla> {
+        if self.status.0 == SystemStatus::初始化 {
+            return None;
         }
+        Some(Lla {
+            latitude: self.latitude.to_f64(),
+            longitude: self.longitude.to_f64(),
+            altitude: self.altitude.to_f64(),
+        })
+    }
+
+    fn velocity(&self) -> Option<Enu> {
+        self.lla()?;
+        Some(Enu {
+            e: self.vel_e.to_f64(),
+            n: self.vel_n.to_f64(),
+            u: self.vel_u.to_f64(),
+        })
+    }
+}
+
+impl GpsTimed for Body {
+    #[inline]
+    fn gps_week_ms(&self) -> (u16, u32) {
+        (self.gps_week, self.gps_time)
     }
 }
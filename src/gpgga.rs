@@ -1,75 +1,92 @@
-﻿use super::BodyParseError;
-use std::str::FromStr;
+﻿use super::{
+    extra, geo::ddmm_to_degrees, opt, time::UtcTime, write_extra, BodyParseError, Fixed, Lla,
+    Padded, ParseMode, Position, Text,
+};
+use core::{fmt, str::FromStr};
 
-#[derive(PartialEq, Debug)]
+/// 定位前接收机发出的 GGA 除定位质量外的字段都是空的，如 `GPGGA,,,,,,0,,,,,,,,`
+#[derive(Clone, PartialEq, Debug)]
 pub struct Body {
-    pub utc_time: u32,
-    pub latitude: (u64, u8),
-    pub ns: NS,
-    pub longitude: (u64, u8),
-    pub ew: EW,
-    pub status: Status,
-    pub nosv: u8,
-    pub hdop: (u16, u8),
-    pub altitude: (u32, u8),
-    pub alt_unit: LenUnit,
-    pub alt_ref: (i32, u8),
-    pub alt_ref_unit: LenUnit,
-    pub diff_age: Option<u8>,
-    pub diff_station: Option<u16>,
-}
-
-#[derive(PartialEq, Debug)]
+    pub utc_time: Option<Fixed>,           // hhmmss.sss，小数位数不限
+    pub latitude: Option<Fixed>,           // ddmm.mmmm
+    pub ns: Option<NS>,                    // 南北半球
+    pub longitude: Option<Fixed>,          // dddmm.mmmm
+    pub ew: Option<EW>,                    // 东西半球
+    pub status: Status,                    // 定位质量
+    pub nosv: Option<Padded<u8>>,          // 使用的卫星数
+    pub hdop: Option<Fixed>,               // 水平精度因子
+    pub altitude: Option<Fixed>,           // 海拔
+    pub alt_unit: Option<LenUnit>,         // 海拔单位
+    pub alt_ref: Option<Fixed>,            // 大地水准面差距
+    pub alt_ref_unit: Option<LenUnit>,     // 大地水准面差距单位
+    pub diff_age: Option<Fixed>,           // 差分数据龄期 / s
+    pub diff_station: Option<Padded<u16>>, // 差分基站号
+    pub extra: Text,                       // 宽松模式下保留的多余字段
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum NS {
     N,
     S,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum EW {
     E,
     W,
 }
 
+code_enum! {
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum Status: crate::code_digit {
+        初始化 = 0x0,
+        单点定位 = 0x1,
+        码差分 = 0x2,
+        PPS定位 = 0x3,
+        固定解 = 0x4,
+        浮点解 = 0x5,
+        正在估算 = 0x6,
+        人工固定值 = 0x7,
+        航位推算模式 = 0x8,
+        WAAS差分 = 0x9,
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
-pub enum Status {
-    初始化 = 0x0,
-    单点定位 = 0x1,
-    码差分 = 0x2,
-    固定解 = 0x4,
-    浮点解 = 0x5,
-    正在估算 = 0x6,
-    人工固定值 = 0x7,
-    航位推算模式 = 0x8,
-    WAAS差分 = 0x9,
-}
-
-#[derive(PartialEq, Debug)]
 pub enum LenUnit {
     M,
 }
 
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
+        let mut s = s.split(',');
+        Ok(Self {
+            utc_time: parse_field!(s =>? "GPGGA:UTCTime", mode),
+            latitude: parse_field!(s =>? "GPGGA:Latitude", mode),
+            ns: parse_field!(s =>? "GPGGA:N", mode),
+            longitude: parse_field!(s =>? "GPGGA:Longitude", mode),
+            ew: parse_field!(s =>? "GPGGA:E", mode),
+            status: parse_field!(s => code "GPGGA:FS", mode),
+            nosv: parse_field!(s =>? "GPGGA:NoSV", mode),
+            hdop: parse_field!(s =>? "GPGGA:HDOP", mode),
+            altitude: parse_field!(s =>? "GPGGA:Altitude", mode),
+            alt_unit: parse_field!(s =>? "GPGGA:AltUnit", mode),
+            alt_ref: parse_field!(s =>? "GPGGA:Altref", mode),
+            alt_ref_unit: parse_field!(s =>? "GPGGA:AltrefUnit", mode),
+            diff_age: parse_field!(s =>? "GPGGA:DiffAge", mode),
+            diff_station: parse_field!(s =>? "GPGGA:DiffStation", mode),
+            extra: extra(all, 14, mode, "GPGGA:Extra")?,
+        })
+    }
+}
+
 impl FromStr for Body {
     type Err = BodyParseError;
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut s = s.split(',');
-        Ok(Self {
-            utc_time: parse_field!(s => "GPGGA:UTCTime"; 2),
-            latitude: parse_field!(s => "GPGGA:Latitude"; ?),
-            ns: parse_field!(s => "GPGGA:N"),
-            longitude: parse_field!(s => "GPGGA:Longitude"; ?),
-            ew: parse_field!(s => "GPGGA:E"),
-            status: parse_field!(s => "GPGGA:FS"),
-            nosv: parse_field!(s => "GPGGA:NoSV"),
-            hdop: parse_field!(s => "GPGGA:HDOP"; ?),
-            altitude: parse_field!(s => "GPGGA:Altitude"; ?),
-            alt_unit: parse_field!(s => "GPGGA:AltUnit"),
-            alt_ref: parse_field!(s => "GPGGA:Altref"; ?),
-            alt_ref_unit: parse_field!(s => "GPGGA:AltrefUnit"),
-            diff_age: parse_field!(s =>? "GPGGA:DiffAge"),
-            diff_station: parse_field!(s =>? "GPGGA:DiffStation"),
-        })
+        Self::parse_with(s, ParseMode::Strict)
     }
 }
 
@@ -105,22 +122,14 @@ impl FromStr for EW {
     }
 }
 
-impl FromStr for Status {
+impl FromStr for LenUnit {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use Status::*;
+        use LenUnit::*;
         match s.as_bytes() {
             [c] => Ok(match c {
-                b'0' => 初始化,
-                b'1' => 单点定位,
-                b'2' => 码差分,
-                b'4' => 固定解,
-                b'5' => 浮点解,
-                b'6' => 正在估算,
-                b'7' => 人工固定值,
-                b'8' => 航位推算模式,
-                b'9' => WAAS差分,
+                b'M' => M,
                 _ => return Err(()),
             }),
             [..] => Err(()),
@@ -128,17 +137,168 @@ impl FromStr for Status {
     }
 }
 
-impl FromStr for LenUnit {
-    type Err = ();
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            opt(self.utc_time),
+            opt(self.latitude.map(|x| x.with_min_width(4))),
+            opt(self.ns),
+            opt(self.longitude.map(|x| x.with_min_width(5))),
+            opt(self.ew),
+            self.status,
+            opt(self.nosv),
+            opt(self.hdop),
+            opt(self.altitude),
+            opt(self.alt_unit),
+            opt(self.alt_ref),
+            opt(self.alt_ref_unit),
+            opt(self.diff_age),
+            opt(self.diff_station),
+        )?;
+        write_extra(f, &self.extra)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use LenUnit::*;
-        match s.as_bytes() {
-            [c] => Ok(match c {
-                b'M' => M,
-                _ => return Err(()),
-            }),
-            [..] => Err(()),
+impl fmt::Display for NS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NS::N => "N",
+            NS::S => "S",
+        })
+    }
+}
+
+impl fmt::Display for EW {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EW::E => "E",
+            EW::W => "W",
+        })
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl fmt::Display for LenUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LenUnit::M => "M",
+        })
+    }
+}
+
+impl Status {
+    /// 是否已经定位，宽松模式下无法识别的定位质量视为未定位
+    #[inline]
+    pub fn is_fixed(&self) -> bool {
+        !matches!(self, Status::初始化 | Status::Other(_))
+    }
+}
+
+impl Position for Body {
+    fn lla(&self) -> Option<Lla> {
+        let (latitude, longitude) = self.lat_lon()?;
+        Some(Lla {
+            latitude,
+            longitude,
+            // 海拔加上大地水准面差距得到椭球高，按浮点数相加以免对齐小数位数时溢出；
+            // 很多接收机不输出大地水准面差距，此时按 0 处理
+            altitude: self.altitude?.to_f64() + self.alt_ref.map_or(0.0, |it| it.to_f64()),
+        })
+    }
+}
+
+impl Body {
+    /// 取与 `reference` 最近的、时刻与本语句一致的时间点
+    ///
+    /// GGA 不带日期，通常以最近一条 RMC 或 ZDA 的时间作为参考。
+    #[inline]
+    pub fn utc_near(&self, reference: UtcTime) -> Option<UtcTime> {
+        reference.with_time_of_day(self.utc_time?)
+    }
+
+    /// 以度为单位的经纬度，未定位时返回 [`None`]
+    pub fn lat_lon(&self) -> Option<(f64, f64)> {
+        if !self.status.is_fixed() {
+            return None;
         }
+        Some((
+            ddmm_to_degrees(self.latitude?, self.ns? == NS::S),
+            ddmm_to_degrees(self.longitude?, self.ew? == EW::W),
+        ))
     }
 }
+
+#[test]
+fn test_parse_no_fix() {
+    use crate::Position;
+
+    const LINE: &str = ",,,,,0,,,,,,,,";
+    let body = LINE.parse::<Body>().unwrap();
+    assert_eq!(body.status, Status::初始化);
+    assert_eq!(body.utc_time, None);
+    assert_eq!(body.latitude, None);
+    assert_eq!(body.nosv, None);
+    assert_eq!(body.lat_lon(), None);
+    assert!(body.lla().is_none());
+    assert_eq!(body.to_string(), LINE);
+
+    // 有时间和卫星数但仍未定位
+    const WAITING: &str = "060220.00,,,,,0,03,,,,,,,";
+    let body = WAITING.parse::<Body>().unwrap();
+    assert_eq!(body.utc_time, Some(Fixed::new(6022000, 2)));
+    assert_eq!(body.nosv, Some(Padded::new(3)));
+    assert!(body.lla().is_none());
+    assert_eq!(body.to_string(), WAITING);
+
+    // 已定位时缺少经纬度也不会给出位置
+    let body = "060220.00,,,,,1,17,1.6,,,,,,".parse::<Body>().unwrap();
+    assert!(body.status.is_fixed());
+    assert_eq!(body.lat_lon(), None);
+
+    // PPS 定位是有效定位
+    let body = "060220.00,3959.55874779,N,11619.61828897,E,3,17,1.6,60.1397,M,-9.2862,M,,"
+        .parse::<Body>()
+        .unwrap();
+    assert_eq!(body.status, Status::PPS定位);
+    assert!(body.lla().is_some());
+
+    // 没有大地水准面差距时直接用海拔
+    const NO_GEOID: &str = "060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,,,,";
+    let body = NO_GEOID.parse::<Body>().unwrap();
+    assert_eq!(body.alt_ref, None);
+    assert_eq!(body.lla().unwrap().altitude, 60.1397);
+    assert_eq!(body.to_string(), NO_GEOID);
+}
+
+#[test]
+fn test_parse_rtk() {
+    use crate::{NmeaLine, Talker};
+
+    // 三位小数的时间和带小数的差分龄期
+    const LINE: &str =
+        "GNGGA,123519.000,3959.55874779,N,11619.61828897,E,4,24,0.6,60.1397,M,-9.2862,M,1.0,0001";
+    let line = LINE.parse::<NmeaLine>().unwrap();
+    let NmeaLine::GGA(Talker::GN, body, _) = &line else {
+        panic!("{:?}", line);
+    };
+    assert_eq!(body.utc_time, Some(Fixed::new(123519000, 3)));
+    assert_eq!(body.status, Status::固定解);
+    assert_eq!(body.diff_age, Some(Fixed::new(10, 1)));
+    assert_eq!(body.diff_station, Some(Padded::new(1)));
+    assert_eq!(
+        body.utc_near(UtcTime(12 * 3600 * 1000)),
+        Some(UtcTime((12 * 3600 + 35 * 60 + 19) * 1000))
+    );
+    assert_eq!(body.to_string(), &LINE[6..]);
+
+    // 没有小数的时间
+    let body = "123519,,,,,0,,,,,,,,".parse::<Body>().unwrap();
+    assert_eq!(body.utc_time, Some(Fixed::new(123519, 0)));
+}
@@ -0,0 +1,115 @@
+﻿use super::{extra, opt, write_extra, BodyParseError, Fixed, Padded, ParseMode, Text};
+use core::{fmt, str::FromStr};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Body {
+    pub mode: Mode,                      // 2D/3D 切换方式
+    pub fix: FixType,                    // 定位类型
+    pub prns: [Option<Padded<u16>>; 12], // 参与解算的卫星号，按通道排列
+    pub pdop: Option<Fixed>,             // 位置精度因子
+    pub hdop: Option<Fixed>,             // 水平精度因子
+    pub vdop: Option<Fixed>,             // 垂直精度因子
+    pub system_id: Option<u8>,           // GNSS 系统号，NMEA 4.1 起才有
+    pub extra: Text,                     // 宽松模式下保留的多余字段
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    手动,
+    自动,
+}
+
+code_enum! {
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum FixType: crate::code_digit {
+        未定位 = 1,
+        二维定位 = 2,
+        三维定位 = 3,
+    }
+}
+
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
+        let mut s = s.split(',');
+        Ok(Self {
+            mode: parse_field!(s => "GPGSA:Smode"),
+            fix: parse_field!(s => code "GPGSA:FS", mode),
+            prns: {
+                let mut prns = [None; 12];
+                for prn in &mut prns {
+                    *prn = parse_field!(s =>? "GPGSA:SV", mode);
+                }
+                prns
+            },
+            pdop: parse_field!(s =>? "GPGSA:PDOP", mode),
+            hdop: parse_field!(s =>? "GPGSA:HDOP", mode),
+            vdop: parse_field!(s =>? "GPGSA:VDOP", mode),
+            system_id: parse_field!(s =>?? "GPGSA:SystemId"),
+            extra: extra(all, 18, mode, "GPGSA:Extra")?,
+        })
+    }
+}
+
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Mode::*;
+        match s.as_bytes() {
+            [c] => Ok(match c {
+                b'M' => 手动,
+                b'A' => 自动,
+                _ => return Err(()),
+            }),
+            [..] => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.mode, self.fix)?;
+        for prn in self.prns {
+            write!(f, ",{}", opt(prn))?;
+        }
+        write!(
+            f,
+            ",{},{},{}",
+            opt(self.pdop),
+            opt(self.hdop),
+            opt(self.vdop),
+        )?;
+        match self.system_id {
+            Some(id) => write!(f, ",{}", id)?,
+            // 多余的字段之前要补上空的系统号
+            None if !self.extra.is_empty() => f.write_str(",")?,
+            None => {}
+        }
+        write_extra(f, &self.extra)
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::手动 => "M",
+            Mode::自动 => "A",
+        })
+    }
+}
+
+impl fmt::Display for FixType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
@@ -0,0 +1,282 @@
+﻿use super::{extra, opt, text_lossy, write_extra, BodyParseError, Padded, ParseMode, Text};
+use core::{fmt, str::FromStr};
+
+#[cfg(feature = "std")]
+use super::Talker;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Body {
+    pub total: u8,                               // 本组语句总数
+    pub index: u8,                               // 本语句序号，从 1 开始
+    pub in_view: Padded<u8>,                     // 可见卫星总数
+    pub satellites: heapless::Vec<Satellite, 4>, // 本语句携带的卫星，最多 4 颗
+    pub padding: u8,                             // 卫星之后用空字段补齐的卫星数
+    pub signal_id: Option<u8>,                   // 信号号，NMEA 4.1 起才有
+    pub extra: Text,                             // 宽松模式下保留的多余字段
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Satellite {
+    pub prn: Padded<u16>,              // 卫星号
+    pub elevation: Option<Padded<i8>>, // 仰角 / °
+    pub azimuth: Option<Padded<u16>>,  // 方位角 / °
+    pub snr: Option<Padded<u8>>,       // 信噪比 / dBHz
+}
+
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
+        let mut s = s.split(',');
+        let total = parse_field!(s => "GPGSV:NoMsg");
+        let index = parse_field!(s => "GPGSV:MsgNo");
+        let in_view = parse_field!(s => "GPGSV:NoSv");
+        // 最多 4 颗卫星和 1 个信号号
+        let mut fields = heapless::Vec::<&str, 17>::new();
+        for field in s {
+            if fields.push(field).is_err() {
+                // 宽松模式下多余的字段留给 extra
+                if mode == ParseMode::Lenient {
+                    break;
+                }
+                return Err(BodyParseError::ParseFailed(
+                    "GPGSV:Satellite",
+                    text_lossy(field),
+                ));
+            }
+        }
+        // 每颗卫星 4 个字段，多出 1 个则是信号号
+        let (satellites, signal_id) = match fields.len() % 4 {
+            0 => (&fields[..], None),
+            1 => {
+                let (satellites, id) = fields.split_at(fields.len() - 1);
+                let id = id[0];
+                match u8::from_str_radix(id, 16) {
+                    Ok(id) => (satellites, Some(id)),
+                    Err(_) => {
+                        return Err(BodyParseError::ParseFailed(
+                            "GPGSV:SignalId",
+                            text_lossy(id),
+                        ))
+                    }
+                }
+            }
+            // 宽松模式下最后一颗卫星缺省的字段为空
+            _ if mode == ParseMode::Lenient => (&fields[..], None),
+            _ => return Err(BodyParseError::MissingField("GPGSV:Satellite")),
+        };
+        // 有的接收机用空字段补齐最后一条语句
+        let padding = satellites
+            .chunks(4)
+            .rev()
+            .take_while(|sv| sv.iter().all(|f| f.is_empty()))
+            .count();
+        Ok(Self {
+            total,
+            index,
+            in_view,
+            satellites: satellites[..satellites.len() - padding * 4]
+                .chunks(4)
+                .map(|sv| {
+                    let mut s = sv.iter().copied();
+                    Ok(Satellite {
+                        prn: parse_field!(s => "GPGSV:SV"),
+                        elevation: parse_field!(s =>? "GPGSV:Elev", mode),
+                        azimuth: parse_field!(s =>? "GPGSV:Az", mode),
+                        snr: parse_field!(s =>? "GPGSV:CN0", mode),
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            padding: padding as u8,
+            signal_id,
+            extra: extra(all, 20, mode, "GPGSV:Extra")?,
+        })
+    }
+}
+
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.total, self.index, self.in_view)?;
+        for sv in &self.satellites {
+            write!(
+                f,
+                ",{},{},{},{}",
+                sv.prn,
+                opt(sv.elevation),
+                opt(sv.azimuth),
+                opt(sv.snr),
+            )?;
+        }
+        for _ in 0..self.padding {
+            f.write_str(",,,,")?;
+        }
+        if let Some(id) = self.signal_id {
+            write!(f, ",{:X}", id)?;
+        }
+        write_extra(f, &self.extra)
+    }
+}
+
+#[cfg(feature = "std")]
+/// 一组 GSV 语句拼成的卫星视图
+#[derive(Clone, PartialEq, Debug)]
+pub struct SkyView {
+    pub talker: Talker,
+    pub signal_id: Option<u8>,
+    pub satellites: Vec<Satellite>,
+}
+
+#[cfg(feature = "std")]
+/// 拼接 GSV 语句时发现的问题
+#[derive(PartialEq, Debug)]
+pub enum GsvError {
+    /// 序号为 0 或超出总数
+    InvalidIndex { total: u8, index: u8 },
+}
+
+#[cfg(feature = "std")]
+/// 还没收齐就被新的一组取代的一组，`missing` 是丢失的序号
+#[derive(Clone, PartialEq, Debug)]
+pub struct Incomplete {
+    pub talker: Talker,
+    pub signal_id: Option<u8>,
+    pub missing: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+/// 加入一条 GSV 语句的结果，两者可能同时出现
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Pushed {
+    pub view: Option<SkyView>,       // 本条语句收齐的一组
+    pub dropped: Option<Incomplete>, // 本条语句开始了新的一组，丢弃的上一组
+}
+
+#[cfg(feature = "std")]
+/// 按发送者和信号号拼接多条 GSV 语句
+///
+/// 同一组内的语句可以乱序到达；
+/// 一组还没收齐就收到了总数不同或序号重复的语句，视为上一组有语句丢失。
+#[derive(Default)]
+pub struct Reassembler {
+    pending: HashMap<Key, Vec<Option<heapless::Vec<Satellite, 4>>>>,
+    views: HashMap<Key, SkyView>,
+}
+
+#[cfg(feature = "std")]
+/// 发送者和信号号
+type Key = (Talker, Option<u8>);
+
+#[cfg(feature = "std")]
+impl Reassembler {
+    /// 加入一条 GSV 语句，一组收齐时返回拼好的视图，同时报告因此丢弃的上一组
+    pub fn push(&mut self, talker: Talker, body: Body) -> Result<Pushed, GsvError> {
+        let Body {
+            total,
+            index,
+            signal_id,
+            satellites,
+            ..
+        } = body;
+        if index == 0 || index > total {
+            return Err(GsvError::InvalidIndex { total, index });
+        }
+
+        let key = (talker, signal_id);
+        let parts = self.pending.entry(key).or_default();
+        // 新的一组开始了，上一组丢弃
+        let mut pushed = Pushed::default();
+        if parts.len() != total as usize || parts[index as usize - 1].is_some() {
+            let missing = (1..=parts.len() as u8)
+                .zip(parts.iter())
+                .filter(|(_, part)| part.is_none())
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            if missing.len() < parts.len() {
+                pushed.dropped = Some(Incomplete {
+                    talker,
+                    signal_id,
+                    missing,
+                });
+            }
+            parts.clear();
+            parts.resize(total as usize, None);
+        }
+        parts[index as usize - 1] = Some(satellites);
+
+        // 收齐了
+        if parts.iter().all(Option::is_some) {
+            let view = SkyView {
+                talker,
+                signal_id,
+                satellites: parts.drain(..).flatten().flatten().collect(),
+            };
+            self.views.insert(key, view.clone());
+            pushed.view = Some(view);
+        }
+        Ok(pushed)
+    }
+
+    /// 某个发送者最近拼好的各信号视图
+    pub fn sky_views(&self, talker: Talker) -> impl Iterator<Item = &SkyView> {
+        self.views
+            .iter()
+            .filter(move |((t, _), _)| *t == talker)
+            .map(|(_, view)| view)
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_reassemble_gpgsv() {
+    const PARTS: [&str; 3] = [
+        "3,1,09,03,03,111,00,04,15,270,00,06,01,010,00,13,06,292,00,1",
+        "3,2,09,14,25,170,00,16,57,208,39,18,67,296,40,19,40,246,00,1",
+        "3,3,09,22,42,067,42,,,,,,,,,,,,,1",
+    ];
+    let parse = |i: usize| PARTS[i].parse::<Body>().unwrap();
+    assert_eq!(parse(2).satellites.len(), 1);
+    assert_eq!(parse(2).signal_id, Some(1));
+
+    let mut reassembler = Reassembler::default();
+    let talker = Talker::GP;
+    // 乱序
+    assert_eq!(reassembler.push(talker, parse(1)), Ok(Pushed::default()));
+    assert_eq!(reassembler.push(talker, parse(0)), Ok(Pushed::default()));
+    let view = reassembler.push(talker, parse(2)).unwrap().view.unwrap();
+    assert_eq!(view.satellites.len(), 9);
+    assert_eq!(view.satellites[5].snr, Some(Padded::new(39)));
+    assert_eq!(reassembler.sky_views(talker).count(), 1);
+    // 丢失
+    let incomplete = Incomplete {
+        talker,
+        signal_id: Some(1),
+        missing: vec![2, 3],
+    };
+    assert_eq!(reassembler.push(talker, parse(0)), Ok(Pushed::default()));
+    assert_eq!(
+        reassembler.push(talker, parse(0)),
+        Ok(Pushed {
+            view: None,
+            dropped: Some(incomplete.clone()),
+        })
+    );
+    // 上一组没收齐时，新的一组只有一条，收齐的视图不会丢失
+    let single = "1,1,01,22,42,067,42,1".parse::<Body>().unwrap();
+    let pushed = reassembler.push(talker, single).unwrap();
+    assert_eq!(pushed.dropped, Some(incomplete));
+    assert_eq!(pushed.view.unwrap().satellites[0].prn, 22);
+    assert_eq!(
+        reassembler.push(talker, "1,2,01".parse().unwrap()),
+        Err(GsvError::InvalidIndex { total: 1, index: 2 })
+    );
+}
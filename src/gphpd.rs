@@ -1,74 +1,130 @@
-﻿use super::BodyParseError;
-use std::str::FromStr;
+﻿use super::{
+    extra, fixed, time::GpsTimed, write_extra, BodyParseError, Enu, Fixed, Lla, ParseMode,
+    Position, Text,
+};
+use core::{fmt, str::FromStr};
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Body {
     pub gps_week: u16,
     pub gps_time: u32,
-    pub heading: u32,
-    pub pitch: i32,
-    pub track: i32,
-    pub latitude: i32,
-    pub longitude: i32,
-    pub altitude: i32,
-    pub vel_e: i32,
-    pub vel_n: i32,
-    pub vel_u: i32,
-    pub baseline: u16,
+    pub heading: Fixed,
+    pub pitch: Fixed,
+    pub track: Fixed,
+    pub latitude: Fixed,
+    pub longitude: Fixed,
+    pub altitude: Fixed,
+    pub vel_e: Fixed,
+    pub vel_n: Fixed,
+    pub vel_u: Fixed,
+    pub baseline: Fixed,
     pub nsv1: u8,
     pub nsv2: u8,
     pub status: Status,
+    pub extra: Text, // 宽松模式下保留的多余字段
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Status {
-    初始化 = 0x0,
-    GPS定位 = 0x3,
-    GPS定向 = 0x4,
-    RTK定位 = 0x5,
-    RTK定向 = 0xB,
+code_enum! {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Status: crate::code_number {
+        初始化 = 0x0,
+        GPS定位 = 0x3,
+        GPS定向 = 0x4,
+        RTK定位 = 0x5,
+        RTK定向 = 0xB,
+    }
 }
 
-impl FromStr for Body {
-    type Err = BodyParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
         let mut s = s.split(',');
         Ok(Self {
             gps_week: parse_field!(s => "GPHPD:GPSWeek"),
             gps_time: parse_field!(s => "GPHPD:GPSTime"; 3),
-            heading: parse_field!(s => "GPHPD:Heading"; 3),
-            pitch: parse_field!(s => "GPHPD:Pitch"; 3),
-            track: parse_field!(s => "GPHPD:Track"; 3),
-            latitude: parse_field!(s => "GPHPD:Latitude"; 7),
-            longitude: parse_field!(s => "GPHPD:Longitude"; 7),
-            altitude: parse_field!(s => "GPHPD:Altitude"; 2),
-            vel_e: parse_field!(s => "GPHPD:Ve"; 3),
-            vel_n: parse_field!(s => "GPHPD:Vn"; 3),
-            vel_u: parse_field!(s => "GPHPD:Vu"; 3),
-            baseline: parse_field!(s => "GPHPD:Baseline"; 3),
+            heading: parse_field!(s => "GPHPD:Heading"),
+            pitch: parse_field!(s => "GPHPD:Pitch"),
+            track: parse_field!(s => "GPHPD:Track"),
+            latitude: parse_field!(s => "GPHPD:Latitude"),
+            longitude: parse_field!(s => "GPHPD:Longitude"),
+            altitude: parse_field!(s => "GPHPD:Altitude"),
+            vel_e: parse_field!(s => "GPHPD:Ve"),
+            vel_n: parse_field!(s => "GPHPD:Vn"),
+            vel_u: parse_field!(s => "GPHPD:Vu"),
+            baseline: parse_field!(s => "GPHPD:Baseline"),
             nsv1: parse_field!(s => "GPHPD:NSV1"),
             nsv2: parse_field!(s => "GPHPD:NSV2"),
-            status: parse_field!(s => "GPHPD:Status"),
+            status: parse_field!(s => code "GPHPD:Status", mode),
+            extra: extra(all, 15, mode, "GPHPD:Extra")?,
         })
     }
 }
 
-impl FromStr for Status {
-    type Err = ();
+impl FromStr for Body {
+    type Err = BodyParseError;
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use Status::*;
-        match s.parse::<u8>() {
-            Ok(n) => Ok(match n {
-                0x0 => 初始化,
-                0x3 => GPS定位,
-                0x4 => GPS定向,
-                0x5 => RTK定位,
-                0xB => RTK定向,
-                _ => return Err(()),
-            }),
-            Err(_) => Err(()),
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.gps_week,
+            fixed(self.gps_time, 3),
+            self.heading,
+            self.pitch,
+            self.track,
+            self.latitude,
+            self.longitude,
+            self.altitude,
+            self.vel_e,
+            self.vel_n,
+            self.vel_u,
+            self.baseline,
+            self.nsv1,
+            self.nsv2,
+            self.status,
+        )?;
+        write_extra(f, &self.extra)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}", self.code())
+    }
+}
+
+impl Position for Body {
+    fn lla(&self) -> Option<Lla> {
+        if self.status == Status::初始化 {
+            return None;
         }
+        Some(Lla {
+            latitude: self.latitude.to_f64(),
+            longitude: self.longitude.to_f64(),
+            altitude: self.altitude.to_f64(),
+        })
+    }
+
+    fn velocity(&self) -> Option<Enu> {
+        self.lla()?;
+        Some(Enu {
+            e: self.vel_e.to_f64(),
+            n: self.vel_n.to_f64(),
+            u: self.vel_u.to_f64(),
+        })
+    }
+}
+
+impl GpsTimed for Body {
+    #[inline]
+    fn gps_week_ms(&self) -> (u16, u32) {
+        (self.gps_week, self.gps_time)
     }
 }
@@ -0,0 +1,212 @@
+﻿use super::{
+    extra,
+    geo::ddmm_to_degrees,
+    gpgga::{EW, NS},
+    opt,
+    time::{Date, UtcTime},
+    write_extra, BodyParseError, Fixed, ParseMode, Text,
+};
+use core::{fmt, str::FromStr};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Body {
+    pub utc_time: Option<Fixed>,  // hhmmss.sss，小数位数不限
+    pub status: Status,           // 定位状态
+    pub latitude: Option<Fixed>,  // ddmm.mmmm
+    pub ns: Option<NS>,           // 南北半球
+    pub longitude: Option<Fixed>, // dddmm.mmmm
+    pub ew: Option<EW>,           // 东西半球
+    pub speed: Option<Fixed>,     // 地面速率 / kn
+    pub course: Option<Fixed>,    // 地面航向 / °
+    pub date: Option<u32>,        // ddmmyy
+    pub mag_var: Option<Fixed>,   // 磁偏角 / °
+    pub mag_var_ew: Option<EW>,   // 磁偏角方向
+    pub mode: Option<Mode>,       // 模式指示，NMEA 2.3 起才有
+    pub extra: Text,              // 宽松模式下保留的多余字段
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    有效,
+    无效,
+}
+
+code_enum! {
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub enum Mode: crate::code_char {
+        自主定位 = b'A',
+        差分 = b'D',
+        估算 = b'E',
+        浮点解 = b'F',
+        固定解 = b'R',
+        手动输入 = b'M',
+        模拟器 = b'S',
+        精密 = b'P',
+        无效 = b'N',
+    }
+}
+
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
+        let mut s = s.split(',');
+        Ok(Self {
+            utc_time: parse_field!(s =>? "GPRMC:UTCTime", mode),
+            status: parse_field!(s => "GPRMC:Status"),
+            latitude: parse_field!(s =>? "GPRMC:Latitude", mode),
+            ns: parse_field!(s =>? "GPRMC:N", mode),
+            longitude: parse_field!(s =>? "GPRMC:Longitude", mode),
+            ew: parse_field!(s =>? "GPRMC:E", mode),
+            speed: parse_field!(s =>? "GPRMC:Spd", mode),
+            course: parse_field!(s =>? "GPRMC:COG", mode),
+            date: parse_field!(s =>? "GPRMC:Date", mode),
+            mag_var: parse_field!(s =>? "GPRMC:MV", mode),
+            mag_var_ew: parse_field!(s =>? "GPRMC:MVE", mode),
+            mode: parse_field!(s =>?? code "GPRMC:Mode", mode),
+            extra: extra(all, 12, mode, "GPRMC:Extra")?,
+        })
+    }
+}
+
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl FromStr for Status {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Status::*;
+        match s.as_bytes() {
+            [c] => Ok(match c {
+                b'A' => 有效,
+                b'V' => 无效,
+                _ => return Err(()),
+            }),
+            [..] => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{:06},{},{}",
+            opt(self.utc_time),
+            self.status,
+            opt(self.latitude.map(|x| x.with_min_width(4))),
+            opt(self.ns),
+            opt(self.longitude.map(|x| x.with_min_width(5))),
+            opt(self.ew),
+            opt(self.speed),
+            opt(self.course),
+            opt(self.date),
+            opt(self.mag_var),
+            opt(self.mag_var_ew),
+        )?;
+        match self.mode {
+            Some(mode) => write!(f, ",{}", mode)?,
+            // 多余的字段之前要补上空的模式指示
+            None if !self.extra.is_empty() => f.write_str(",")?,
+            None => {}
+        }
+        write_extra(f, &self.extra)
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::有效 => "A",
+            Status::无效 => "V",
+        })
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code() as char)
+    }
+}
+
+impl Body {
+    #[inline]
+    pub fn utc(&self) -> Option<UtcTime> {
+        UtcTime::from_date_time(Date::from_ddmmyy(self.date?)?, self.utc_time?)
+    }
+
+    /// 以度为单位的经纬度，定位无效时返回 [`None`]
+    pub fn lat_lon(&self) -> Option<(f64, f64)> {
+        if self.status != Status::有效 {
+            return None;
+        }
+        Some((
+            ddmm_to_degrees(self.latitude?, self.ns? == NS::S),
+            ddmm_to_degrees(self.longitude?, self.ew? == EW::W),
+        ))
+    }
+}
+
+#[test]
+fn test_parse_gprmc() {
+    use crate::{NmeaLine, Talker};
+
+    let line = "GPRMC,060220.00,A,3959.55874779,N,11619.61828897,E,0.012,,170621,,,D"
+        .parse::<NmeaLine>()
+        .unwrap();
+    assert_eq!(
+        line,
+        NmeaLine::RMC(
+            Talker::GP,
+            Body {
+                utc_time: Some(Fixed::new(6022000, 2)),
+                status: Status::有效,
+                latitude: Some(Fixed::new(395955874779, 8)),
+                ns: Some(NS::N),
+                longitude: Some(Fixed::new(1161961828897, 8)),
+                ew: Some(EW::E),
+                speed: Some(Fixed::new(12, 3)),
+                course: None,
+                date: Some(170621),
+                mag_var: None,
+                mag_var_ew: None,
+                mode: Some(Mode::差分),
+                extra: Default::default(),
+            }
+        )
+    );
+
+    // NMEA 2.3 之前没有模式指示
+    let body = "000012.00,V,,,,,,,170621,,".parse::<Body>().unwrap();
+    assert_eq!(body.status, Status::无效);
+    assert_eq!(body.latitude, None);
+    assert_eq!(body.mode, None);
+
+    // 定位前时间和日期都是空的
+    const NO_FIX: &str = ",V,,,,,,,,,,N";
+    for mode in [ParseMode::Strict, ParseMode::Lenient] {
+        let body = Body::parse_with(NO_FIX, mode).unwrap();
+        assert_eq!(body.utc_time, None);
+        assert_eq!(body.date, None);
+        assert_eq!(body.mode, Some(Mode::无效));
+        assert_eq!(body.utc(), None);
+        assert_eq!(body.to_string(), NO_FIX);
+    }
+
+    // 时间的小数位数不限
+    for (time, ms) in [("060220", 0), ("060220.5", 500), ("060220.125", 125)] {
+        let line = format!(
+            "{},A,3959.55874779,N,11619.61828897,E,0.012,,170621,,,D",
+            time
+        );
+        let body = line.parse::<Body>().unwrap();
+        assert_eq!(body.utc().unwrap().0 % 1000, ms);
+        assert_eq!(body.to_string(), line);
+    }
+}
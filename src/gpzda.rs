@@ -0,0 +1,70 @@
+﻿use super::{
+    extra, opt,
+    time::{Date, UtcTime},
+    write_extra, BodyParseError, Fixed, Padded, ParseMode, Text,
+};
+use core::{fmt, str::FromStr};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Body {
+    pub utc_time: Fixed,                  // hhmmss.sss，小数位数不限
+    pub day: u8,                          // 日
+    pub month: u8,                        // 月
+    pub year: u16,                        // 年
+    pub zone_hours: Option<Padded<i8>>,   // 本地时区小时
+    pub zone_minutes: Option<Padded<u8>>, // 本地时区分钟
+    pub extra: Text,                      // 宽松模式下保留的多余字段
+}
+
+impl Body {
+    #[inline]
+    pub fn date(&self) -> Option<Date> {
+        Date::new(self.year, self.month, self.day)
+    }
+
+    #[inline]
+    pub fn utc(&self) -> Option<UtcTime> {
+        UtcTime::from_date_time(self.date()?, self.utc_time)
+    }
+}
+
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
+        let mut s = s.split(',');
+        Ok(Self {
+            utc_time: parse_field!(s => "GPZDA:UTCTime"),
+            day: parse_field!(s => "GPZDA:Day"),
+            month: parse_field!(s => "GPZDA:Month"),
+            year: parse_field!(s => "GPZDA:Year"),
+            zone_hours: parse_field!(s =>? "GPZDA:LTZH", mode),
+            zone_minutes: parse_field!(s =>? "GPZDA:LTZN", mode),
+            extra: extra(all, 6, mode, "GPZDA:Extra")?,
+        })
+    }
+}
+
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{:02},{:02},{:04},{},{}",
+            self.utc_time,
+            self.day,
+            self.month,
+            self.year,
+            opt(self.zone_hours),
+            opt(self.zone_minutes),
+        )?;
+        write_extra(f, &self.extra)
+    }
+}
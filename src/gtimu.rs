@@ -1,34 +1,70 @@
-﻿use super::BodyParseError;
-use std::str::FromStr;
+﻿use super::{extra, fixed, time::GpsTimed, write_extra, BodyParseError, Fixed, ParseMode, Text};
+use core::{fmt, str::FromStr};
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Body {
     pub gps_week: u16,
     pub gps_time: u32,
-    pub gyro_x: i32,
-    pub gyro_y: i32,
-    pub gyro_z: i32,
-    pub acc_x: i32,
-    pub acc_y: i32,
-    pub acc_z: i32,
-    pub tpr: i16,
+    pub gyro_x: Fixed,
+    pub gyro_y: Fixed,
+    pub gyro_z: Fixed,
+    pub acc_x: Fixed,
+    pub acc_y: Fixed,
+    pub acc_z: Fixed,
+    pub tpr: Fixed,
+    pub extra: Text, // 宽松模式下保留的多余字段
 }
 
-impl FromStr for Body {
-    type Err = BodyParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Body {
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let all = s;
         let mut s = s.split(',');
         Ok(Self {
             gps_week: parse_field!(s => "GTIMU:GPSWeek"),
             gps_time: parse_field!(s => "GTIMU:GPSTime"; 3),
-            gyro_x: parse_field!(s => "GTIMU:GyroX"; 4),
-            gyro_y: parse_field!(s => "GTIMU:GyroY"; 4),
-            gyro_z: parse_field!(s => "GTIMU:GyroZ"; 4),
-            acc_x: parse_field!(s => "GTIMU:AccX"; 4),
-            acc_y: parse_field!(s => "GTIMU:AccY"; 4),
-            acc_z: parse_field!(s => "GTIMU:AccZ"; 4),
-            tpr: parse_field!(s => "GTIMU:Tpr"; 1),
+            gyro_x: parse_field!(s => "GTIMU:GyroX"),
+            gyro_y: parse_field!(s => "GTIMU:GyroY"),
+            gyro_z: parse_field!(s => "GTIMU:GyroZ"),
+            acc_x: parse_field!(s => "GTIMU:AccX"),
+            acc_y: parse_field!(s => "GTIMU:AccY"),
+            acc_z: parse_field!(s => "GTIMU:AccZ"),
+            tpr: parse_field!(s => "GTIMU:Tpr"),
+            extra: extra(all, 9, mode, "GTIMU:Extra")?,
         })
     }
 }
+
+impl FromStr for Body {
+    type Err = BodyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl fmt::Display for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{}",
+            self.gps_week,
+            fixed(self.gps_time, 3),
+            self.gyro_x,
+            self.gyro_y,
+            self.gyro_z,
+            self.acc_x,
+            self.acc_y,
+            self.acc_z,
+            self.tpr,
+        )?;
+        write_extra(f, &self.extra)
+    }
+}
+
+impl GpsTimed for Body {
+    #[inline]
+    fn gps_week_ms(&self) -> (u16, u32) {
+        (self.gps_week, self.gps_time)
+    }
+}
@@ -1,34 +1,69 @@
-use std::str::FromStr;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// 没有 `std` 时错误中的文本是定长的，不可避免地较大
+#![cfg_attr(not(feature = "std"), allow(clippy::result_large_err))]
+
+use core::{fmt, str::FromStr};
 
 macro_rules! parse_field {
+    // 解析代码，宽松模式下无法识别的代码保留为 `Other`
+    ($s:ident => code $info:expr, $mode:expr) => {
+        match $s.next() {
+            Some(s) => match crate::parse_code(s, $mode) {
+                Some(it) => it,
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
+            },
+            None => return Err(BodyParseError::MissingField($info)),
+        }
+    };
+    // 解析可空的代码，可以整个缺省
+    ($s:ident =>?? code $info:expr, $mode:expr) => {
+        match $s.next() {
+            Some("") | None => None,
+            Some(s) => match crate::parse_code(s, $mode) {
+                Some(it) => Some(it),
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
+            },
+        }
+    };
+    // 解析可空数据，宽松模式下可以整个缺省
+    ($s:ident =>? $info:expr, $mode:expr) => {
+        match $s.next() {
+            Some(s) => match crate::parse_option(s) {
+                Some(it) => it,
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
+            },
+            None if $mode == crate::ParseMode::Lenient => None,
+            None => return Err(BodyParseError::MissingField($info)),
+        }
+    };
     // 解析一般数据
     ($s:ident => $info:expr) => {
         match $s.next() {
             Some(s) => match crate::parse_any(s) {
                 Some(it) => it,
-                None => return Err(BodyParseError::ParseFailed($info, s.to_string())),
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
             },
             None => return Err(BodyParseError::MissingField($info)),
         }
     };
-    // 解析一般数据
+    // 解析可空数据
     ($s:ident =>? $info:expr) => {
         match $s.next() {
             Some(s) => match crate::parse_option(s) {
                 Some(it) => it,
-                None => return Err(BodyParseError::ParseFailed($info, s.to_string())),
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
             },
             None => return Err(BodyParseError::MissingField($info)),
         }
     };
-    // 定点小数解析为整数
-    ($s:ident => $info:expr; ?) => {
+    // 解析可以整个缺省的尾部字段
+    ($s:ident =>?? $info:expr) => {
         match $s.next() {
-            Some(s) => match crate::parse_fixed_unknown(s) {
+            Some(s) => match crate::parse_option(s) {
                 Some(it) => it,
-                None => return Err(BodyParseError::ParseFailed($info, s.to_string())),
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
             },
-            None => return Err(BodyParseError::MissingField($info)),
+            None => None,
         }
     };
     // 定点小数解析为整数
@@ -37,70 +72,453 @@ macro_rules! parse_field {
         match $s.next() {
             Some(s) => match crate::parse_fixed(s, $n) {
                 Some(it) => it,
-                None => return Err(BodyParseError::ParseFailed($info, s.to_string())),
+                None => return Err(BodyParseError::ParseFailed($info, crate::text_lossy(s))),
             },
             None => return Err(BodyParseError::MissingField($info)),
         }
     };
 }
 
+/// 定义用代码表示的枚举，`read` 从文本读出代码
+///
+/// 生成的枚举带有 `Other(u8)`，只在宽松模式下出现；[`FromStr`] 按严格模式解析。
+macro_rules! code_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident: $read:path {
+            $($variant:ident = $code:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($variant,)*
+            /// 无法识别的代码
+            Other(u8),
+        }
+
+        impl $name {
+            /// 代码对应的值，无法识别的代码为 `Other`
+            pub const fn from_code(code: u8) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    code => Self::Other(code),
+                }
+            }
+
+            /// 值对应的代码
+            pub const fn code(&self) -> u8 {
+                match *self {
+                    $(Self::$variant => $code,)*
+                    Self::Other(code) => code,
+                }
+            }
+        }
+
+        impl crate::Code for $name {
+            #[inline]
+            fn read(s: &str) -> Option<Self> {
+                $read(s).map(Self::from_code)
+            }
+
+            #[inline]
+            fn is_other(&self) -> bool {
+                matches!(self, Self::Other(_))
+            }
+        }
+
+        impl core::str::FromStr for $name {
+            type Err = ();
+
+            #[inline]
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                crate::parse_code(s, crate::ParseMode::Strict).ok_or(())
+            }
+        }
+    };
+}
+
 pub mod cmd;
+#[cfg(feature = "async")]
+mod codec;
+mod fixed;
+#[cfg(feature = "std")]
+mod gcj;
+mod geo;
+pub mod gpchc;
 pub mod gpfpd;
 pub mod gpgga;
+pub mod gpgsa;
+pub mod gpgsv;
 pub mod gphpd;
+pub mod gprmc;
+pub mod gpzda;
 pub mod gtimu;
+mod padded;
 mod parser;
+#[cfg(feature = "std")]
+pub mod projection;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod registry;
+mod talker;
+pub mod time;
+#[cfg(feature = "std")]
+mod vec_parser;
 
-pub use parser::NmeaParser;
+#[cfg(feature = "async")]
+pub use codec::NmeaCodec;
+pub use fixed::Fixed;
+pub use geo::{ddmm_to_degrees, Enu, Lla, Position, WGS84_A, WGS84_F};
+#[cfg(feature = "std")]
+pub use geo::{Ecef, EnuFrame};
+pub use padded::Padded;
+pub use parser::{
+    ChecksumPolicy, ChecksumRules, Diagnostics, FrameError, Framing, NmeaParser, ParseEvent,
+    ParsedSentence,
+};
+#[cfg(feature = "std")]
+pub use reader::NmeaReader;
+#[cfg(feature = "std")]
+pub use registry::{CustomBody, SentenceBody, SentenceRegistry};
+pub use talker::Talker;
+#[cfg(feature = "std")]
+pub use vec_parser::NmeaVecParser;
+
+/// 语句中保留的文本，有 `std` 时是 [`String`]，否则是容量为 [`TEXT_CAPACITY`] 的 [`heapless::String`]
+#[cfg(feature = "std")]
+pub type Text = String;
+/// 语句中保留的文本，有 `std` 时是 `String`，否则是容量为 [`TEXT_CAPACITY`] 的 [`heapless::String`]
+///
+/// 更长的文本只能通过借用原文的 [`NmeaLineRef`] 取得。
+#[cfg(not(feature = "std"))]
+pub type Text = heapless::String<TEXT_CAPACITY>;
+
+/// 诊断中保留的原始字节，有 `std` 时是 [`Vec`]，否则是容量为 [`TEXT_CAPACITY`] 的 [`heapless::Vec`]
+#[cfg(feature = "std")]
+pub type RawBytes = Vec<u8>;
+/// 诊断中保留的原始字节，有 `std` 时是 `Vec`，否则是容量为 [`TEXT_CAPACITY`] 的 [`heapless::Vec`]
+#[cfg(not(feature = "std"))]
+pub type RawBytes = heapless::Vec<u8, TEXT_CAPACITY>;
+
+/// 没有 `std` 时 [`Text`] 和 [`RawBytes`] 的容量
+pub const TEXT_CAPACITY: usize = 128;
+
+/// 语句体的解析模式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// 无法识别的代码和缺失的字段都是错误，多余的尾部字段被忽略
+    #[default]
+    Strict,
+    /// 无法识别的代码解析为 `Other`，整个缺省的可空字段解析为 [`None`]，多余的尾部字段保留在 `extra` 中
+    Lenient,
+}
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum BodyParseError {
     MissingField(&'static str),
-    ParseFailed(&'static str, String),
+    ParseFailed(&'static str, Text),
+    /// 没有 `std` 时，需要保留的文本超过了 [`TEXT_CAPACITY`]
+    TooLong(&'static str),
 }
 
 /// 来自星网宇达的 NMEA 消息类型
+///
+/// 以后可能增加新的语句，因此不能穷尽匹配。
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum NmeaLine {
     GPFPD(gpfpd::Body),
     GTIMU(gtimu::Body),
     GPHPD(gphpd::Body),
-    GPGGA(gpgga::Body, String),
-    GPRMC(String),
-    GPCHC(String),
+    GGA(Talker, gpgga::Body, Text),
+    RMC(Talker, gprmc::Body),
+    GSA(Talker, gpgsa::Body),
+    GSV(Talker, gpgsv::Body),
+    ZDA(Talker, gpzda::Body),
+    GPCHC(gpchc::Body),
     CMD(cmd::Body),
-    Unknown(String, String),
+    /// 由 [`SentenceRegistry`] 中注册的语句解析，带有语句头
+    #[cfg(feature = "std")]
+    Custom(Text, CustomBody),
+    Unknown(Text, Text),
+}
+
+/// 借用原文的 [`NmeaLine`]，解析时不分配内存
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum NmeaLineRef<'a> {
+    GPFPD(gpfpd::Body),
+    GTIMU(gtimu::Body),
+    GPHPD(gphpd::Body),
+    GGA(Talker, gpgga::Body, &'a str),
+    RMC(Talker, gprmc::Body),
+    GSA(Talker, gpgsa::Body),
+    GSV(Talker, gpgsv::Body),
+    ZDA(Talker, gpzda::Body),
+    GPCHC(gpchc::Body),
+    CMD(cmd::BodyRef<'a>),
+    /// 由 [`SentenceRegistry`] 中注册的语句解析，带有语句头
+    #[cfg(feature = "std")]
+    Custom(&'a str, CustomBody),
+    Unknown(&'a str, &'a str),
+}
+
+impl<'a> NmeaLineRef<'a> {
+    /// 按严格模式解析去掉 `$` 和校验和的语句，没有 `,` 的语句只有语句头
+    #[inline]
+    pub fn parse(s: &'a str) -> Result<Self, BodyParseError> {
+        Self::parse_with(s, ParseMode::Strict)
+    }
+
+    /// 按指定模式解析去掉 `$` 和校验和的语句
+    pub fn parse_with(s: &'a str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        let (head, tail) = s.split_once(',').unwrap_or((s, ""));
+        Ok(match head {
+            "GPFPD" => Self::GPFPD(gpfpd::Body::parse_with(tail, mode)?),
+            "GTIMU" => Self::GTIMU(gtimu::Body::parse_with(tail, mode)?),
+            "GPHPD" => Self::GPHPD(gphpd::Body::parse_with(tail, mode)?),
+            "GPCHC" => Self::GPCHC(gpchc::Body::parse_with(tail, mode)?),
+            "cmd" => Self::CMD(cmd::BodyRef::parse(tail)?),
+            head => match talker::split_head(head) {
+                Some((talker, "GGA")) => {
+                    Self::GGA(talker, gpgga::Body::parse_with(tail, mode)?, tail)
+                }
+                Some((talker, "RMC")) => Self::RMC(talker, gprmc::Body::parse_with(tail, mode)?),
+                Some((talker, "GSA")) => Self::GSA(talker, gpgsa::Body::parse_with(tail, mode)?),
+                Some((talker, "GSV")) => Self::GSV(talker, gpgsv::Body::parse_with(tail, mode)?),
+                Some((talker, "ZDA")) => Self::ZDA(talker, gpzda::Body::parse_with(tail, mode)?),
+                _ => Self::Unknown(head, tail),
+            },
+        })
+    }
+
+    /// 复制为 [`NmeaLine`]，没有 `std` 时文本可能超过容量
+    #[inline]
+    pub fn try_to_owned(&self) -> Result<NmeaLine, BodyParseError> {
+        self.clone().try_into_owned()
+    }
+
+    /// 转换为 [`NmeaLine`]，语句体直接移动，只复制借用的文本
+    pub fn try_into_owned(self) -> Result<NmeaLine, BodyParseError> {
+        Ok(match self {
+            Self::GPFPD(body) => NmeaLine::GPFPD(body),
+            Self::GTIMU(body) => NmeaLine::GTIMU(body),
+            Self::GPHPD(body) => NmeaLine::GPHPD(body),
+            Self::GGA(talker, body, tail) => NmeaLine::GGA(talker, body, text(tail, "GGA:Tail")?),
+            Self::RMC(talker, body) => NmeaLine::RMC(talker, body),
+            Self::GSA(talker, body) => NmeaLine::GSA(talker, body),
+            Self::GSV(talker, body) => NmeaLine::GSV(talker, body),
+            Self::ZDA(talker, body) => NmeaLine::ZDA(talker, body),
+            Self::GPCHC(body) => NmeaLine::GPCHC(body),
+            Self::CMD(body) => NmeaLine::CMD(body.try_to_owned()?),
+            #[cfg(feature = "std")]
+            Self::Custom(head, body) => NmeaLine::Custom(head.into(), body),
+            Self::Unknown(head, tail) => {
+                NmeaLine::Unknown(text(head, "Unknown:Head")?, text(tail, "Unknown:Tail")?)
+            }
+        })
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_owned(&self) -> NmeaLine {
+        self.try_to_owned().expect("String has no capacity limit")
+    }
+}
+
+impl NmeaLine {
+    /// 按指定模式解析去掉 `$` 和校验和的语句
+    #[inline]
+    pub fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError> {
+        NmeaLineRef::parse_with(s, mode)?.try_into_owned()
+    }
 }
 
 impl FromStr for NmeaLine {
     type Err = BodyParseError;
 
+    #[inline]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (head, tail) = s.split_once(',').unwrap();
-        Ok(match head {
-            "GPFPD" => NmeaLine::GPFPD(tail.parse()?),
-            "GTIMU" => NmeaLine::GTIMU(tail.parse()?),
-            "GPHPD" => NmeaLine::GPHPD(tail.parse()?),
-            "GPGGA" => NmeaLine::GPGGA(tail.parse()?, tail.into()),
-            "GPRMC" => NmeaLine::GPRMC(tail.into()),
-            "GPCHC" => NmeaLine::GPCHC(tail.into()),
-            "cmd" => NmeaLine::CMD(tail.parse()?),
-            unknown => NmeaLine::Unknown(unknown.into(), tail.into()),
-        })
+        Self::parse_with(s, ParseMode::Strict)
+    }
+}
+
+impl fmt::Display for NmeaLine {
+    /// 编码为完整的语句，包括校验和与行尾
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (talker, head, tail): (_, &str, &dyn fmt::Display) = match self {
+            NmeaLine::GPFPD(body) => (None, "GPFPD", body),
+            NmeaLine::GTIMU(body) => (None, "GTIMU", body),
+            NmeaLine::GPHPD(body) => (None, "GPHPD", body),
+            NmeaLine::GGA(talker, body, _) => (Some(talker), "GGA", body),
+            NmeaLine::RMC(talker, body) => (Some(talker), "RMC", body),
+            NmeaLine::GSA(talker, body) => (Some(talker), "GSA", body),
+            NmeaLine::GSV(talker, body) => (Some(talker), "GSV", body),
+            NmeaLine::ZDA(talker, body) => (Some(talker), "ZDA", body),
+            NmeaLine::GPCHC(body) => (None, "GPCHC", body),
+            NmeaLine::CMD(body) => return write!(f, "{}", body),
+            #[cfg(feature = "std")]
+            NmeaLine::Custom(head, body) => (None, head, body),
+            NmeaLine::Unknown(head, tail) => (None, head, tail),
+        };
+        write_nmea(f, talker, head, tail)
+    }
+}
+
+impl fmt::Display for NmeaLineRef<'_> {
+    /// 编码为完整的语句，包括校验和与行尾
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (talker, head, tail): (_, &str, &dyn fmt::Display) = match self {
+            Self::GPFPD(body) => (None, "GPFPD", body),
+            Self::GTIMU(body) => (None, "GTIMU", body),
+            Self::GPHPD(body) => (None, "GPHPD", body),
+            Self::GGA(talker, body, _) => (Some(talker), "GGA", body),
+            Self::RMC(talker, body) => (Some(talker), "RMC", body),
+            Self::GSA(talker, body) => (Some(talker), "GSA", body),
+            Self::GSV(talker, body) => (Some(talker), "GSV", body),
+            Self::ZDA(talker, body) => (Some(talker), "ZDA", body),
+            Self::GPCHC(body) => (None, "GPCHC", body),
+            Self::CMD(body) => return write!(f, "{}", body),
+            #[cfg(feature = "std")]
+            Self::Custom(head, body) => (None, head, body),
+            Self::Unknown(head, tail) => (None, head, tail),
+        };
+        write_nmea(f, talker, head, tail)
+    }
+}
+
+/// 写出完整的语句，先格式化一遍计算校验和，避免分配
+fn write_nmea(
+    f: &mut fmt::Formatter<'_>,
+    talker: Option<&Talker>,
+    head: &str,
+    tail: &dyn fmt::Display,
+) -> fmt::Result {
+    let mut cs = Xor(0);
+    fmt::Write::write_fmt(&mut cs, format_args!("{}{},{}", opt(talker), head, tail))?;
+    write!(f, "${}{},{}*{:02X}\r\n", opt(talker), head, tail, cs.0)
+}
+
+/// 计算写入文本的异或校验和
+struct Xor(u8);
+
+impl fmt::Write for Xor {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 = s.bytes().fold(self.0, |sum, it| sum ^ it);
+        Ok(())
     }
 }
 
+#[cfg(feature = "std")]
 #[inline]
 pub fn rebuild_nema(head: &str, tail: &str, cs: u8) -> String {
-    format!("${},{}*{:2X}", head, tail, cs)
+    format!("${},{}*{:02X}", head, tail, cs)
+}
+
+/// 编码一条完整的语句，计算校验和并加上行尾
+#[cfg(feature = "std")]
+pub fn encode_nmea(head: &str, tail: &str) -> String {
+    let cs = head
+        .bytes()
+        .chain(Some(b','))
+        .chain(tail.bytes())
+        .fold(0, |sum, it| sum ^ it);
+    format!("${},{}*{:02X}\r\n", head, tail, cs)
 }
 
+/// 复制需要保留的文本，没有 `std` 时超过容量报错
+#[cfg(feature = "std")]
 #[inline]
-fn parse_any<T: FromStr>(s: &str) -> Option<T> {
-    match s.parse::<T>() {
-        Ok(it) => Some(it),
-        Err(_) => None,
+fn text(s: &str, _: &'static str) -> Result<Text, BodyParseError> {
+    Ok(s.into())
+}
+
+/// 复制需要保留的文本，没有 `std` 时超过容量报错
+#[cfg(not(feature = "std"))]
+#[inline]
+fn text(s: &str, info: &'static str) -> Result<Text, BodyParseError> {
+    Text::try_from(s).map_err(|_| BodyParseError::TooLong(info))
+}
+
+/// 复制用于诊断的文本，没有 `std` 时截断到容量以内
+#[cfg(feature = "std")]
+#[inline]
+fn text_lossy(s: &str) -> Text {
+    s.into()
+}
+
+/// 复制用于诊断的文本，没有 `std` 时截断到容量以内
+#[cfg(not(feature = "std"))]
+fn text_lossy(s: &str) -> Text {
+    let mut end = s.len().min(TEXT_CAPACITY);
+    while !s.is_char_boundary(end) {
+        end -= 1;
     }
+    Text::try_from(&s[..end]).unwrap_or_default()
+}
+
+/// 把可能不是 UTF-8 的原始字节转换为诊断用的文本
+#[cfg(feature = "std")]
+#[inline]
+fn text_from_utf8_lossy(bytes: &[u8]) -> Text {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// 把可能不是 UTF-8 的原始字节转换为诊断用的文本
+#[cfg(not(feature = "std"))]
+fn text_from_utf8_lossy(bytes: &[u8]) -> Text {
+    let mut text = Text::new();
+    for chunk in bytes.utf8_chunks() {
+        if text.push_str(chunk.valid()).is_err()
+            || (!chunk.invalid().is_empty() && text.push('\u{FFFD}').is_err())
+        {
+            break;
+        }
+    }
+    text
+}
+
+/// 复制诊断用的原始字节，没有 `std` 时截断到容量以内
+#[cfg(feature = "std")]
+#[inline]
+fn raw_bytes(bytes: &[u8]) -> RawBytes {
+    bytes.to_vec()
+}
+
+/// 复制诊断用的原始字节，没有 `std` 时截断到容量以内
+#[cfg(not(feature = "std"))]
+#[inline]
+fn raw_bytes(bytes: &[u8]) -> RawBytes {
+    RawBytes::from_slice(&bytes[..bytes.len().min(TEXT_CAPACITY)]).unwrap_or_default()
+}
+
+#[inline]
+fn fixed(x: impl Into<i128>, n: u8) -> Fixed {
+    Fixed::new(x.into(), n)
+}
+
+/// 可空字段，空时不输出任何内容
+struct Opt<T>(Option<T>);
+
+impl<T: fmt::Display> fmt::Display for Opt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(it) => it.fmt(f),
+            None => Ok(()),
+        }
+    }
+}
+
+#[inline]
+fn opt<T>(x: Option<T>) -> Opt<T> {
+    Opt(x)
+}
+
+#[inline]
+fn parse_any<T: FromStr>(s: &str) -> Option<T> {
+    s.parse::<T>().ok()
 }
 
 #[inline]
@@ -115,37 +533,74 @@ fn parse_option<T: FromStr>(s: &str) -> Option<Option<T>> {
     }
 }
 
-fn parse_fixed_unknown<T: FromStr>(s: &str) -> Option<(T, u8)> {
-    let b = s.as_bytes();
-    let n = match b.iter().rev().enumerate().find(|(_, b)| **b == b'.') {
-        Some((n, _)) => n,
-        None => return None,
-    };
-    let i = b.len() - n - 1;
-    let mut buf = [0u8; 16];
-    buf[..i].copy_from_slice(&b[..i]);
-    buf[i..][..n].copy_from_slice(&b[i + 1..]);
-    match unsafe { std::str::from_utf8_unchecked(&buf[..b.len() - 1]) }.parse() {
-        Ok(x) => Some((x, n as u8)),
-        Err(_) => None,
+/// 用代码表示的枚举，见 `code_enum!`
+trait Code: Sized {
+    /// 读出代码，格式错误时返回 [`None`]，无法识别的代码读为 `Other`
+    fn read(s: &str) -> Option<Self>;
+
+    fn is_other(&self) -> bool;
+}
+
+#[inline]
+fn parse_code<T: Code>(s: &str, mode: ParseMode) -> Option<T> {
+    T::read(s).filter(|it| mode == ParseMode::Lenient || !it.is_other())
+}
+
+/// 一位十进制数字的代码
+#[inline]
+fn code_digit(s: &str) -> Option<u8> {
+    match s.as_bytes() {
+        [c @ b'0'..=b'9'] => Some(c - b'0'),
+        _ => None,
+    }
+}
+
+/// 一位十六进制数字的代码，不区分大小写
+#[inline]
+fn code_hex(s: &str) -> Option<u8> {
+    match s.as_bytes() {
+        [c] => (*c as char).to_digit(16).map(|d| d as u8),
+        _ => None,
+    }
+}
+
+/// 十进制数的代码
+#[inline]
+fn code_number(s: &str) -> Option<u8> {
+    s.parse().ok()
+}
+
+/// 一个 ASCII 字符的代码
+#[inline]
+fn code_char(s: &str) -> Option<u8> {
+    match s.as_bytes() {
+        [c] if c.is_ascii_graphic() => Some(*c),
+        _ => None,
     }
 }
 
-fn parse_fixed<T: FromStr>(s: &str, n: usize) -> Option<T> {
-    let b = s.as_bytes();
-    if b.len() < n + 2 {
-        return None;
+/// 前 `n` 个字段之后多余的部分，只在宽松模式下保留
+fn extra(s: &str, n: usize, mode: ParseMode, info: &'static str) -> Result<Text, BodyParseError> {
+    match mode {
+        ParseMode::Strict => Ok(Text::new()),
+        ParseMode::Lenient => text(s.splitn(n + 1, ',').nth(n).unwrap_or(""), info),
     }
-    let i = b.len() - n - 1;
-    if b[i] != b'.' {
-        return None;
+}
+
+/// 写出多余的尾部字段
+#[inline]
+fn write_extra(f: &mut fmt::Formatter<'_>, extra: &str) -> fmt::Result {
+    if extra.is_empty() {
+        Ok(())
+    } else {
+        write!(f, ",{}", extra)
     }
-    let mut buf = [0u8; 16];
-    buf[..i].copy_from_slice(&b[..i]);
-    buf[i..][..n].copy_from_slice(&b[i + 1..]);
-    match unsafe { std::str::from_utf8_unchecked(&buf[..b.len() - 1]) }.parse() {
-        Ok(x) => Some(x),
-        Err(_) => None,
+}
+
+fn parse_fixed<T: TryFrom<i128>>(s: &str, n: u8) -> Option<T> {
+    match s.parse::<Fixed>() {
+        Ok(x) if x.scale() == n => x.value().try_into().ok(),
+        _ => None,
     }
 }
 
@@ -175,6 +630,7 @@ fn test_parse() {
     assert_eq!(parser.next(), None);
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_rebuild_gpgga() {
     const LINE: &str =
@@ -183,9 +639,107 @@ fn test_rebuild_gpgga() {
     let mut parser = NmeaParser::<256>::default();
     parser.as_buf()[..LINE.len()].copy_from_slice(LINE.as_bytes());
     parser.notify_received(LINE.len());
-    if let Some((NmeaLine::GPGGA(_, tail), cs)) = parser.next() {
+    if let Some((NmeaLine::GGA(_, _, tail), cs)) = parser.next() {
         assert_eq!(rebuild_nema("GPGGA", tail.as_str(), cs), LINE);
     } else {
         panic!("Parse failed.");
     }
+    assert_eq!(rebuild_nema("GPGGA", "", 0x0A), "$GPGGA,*0A");
+}
+
+#[test]
+fn test_encode() {
+    const LINES: [&str; 19] = [
+        "$GPFPD,2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,-308580.94,0.003,-0.033,-3243.491,10.191,15,18,04*63",
+        "$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C",
+        "$GPHPD,0,0.000,0.000,0.000,0.000,0.0000000,0.0000000,0.00,0.000,0.000,0.000,0.000,0,0,00*49",
+        "$GPGGA,060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42",
+        "$GPRMC,060220.00,A,3959.55874779,N,11619.61828897,E,0.012,,170621,,,D*7A",
+        "$GNGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1,1*3A",
+        "$GPGSV,3,3,09,22,42,067,42,1*5C",
+        "$GNZDA,060212.40,22,11,2021,,*7A",
+        // 保留原文的位数和补齐用的空字段
+        "$GNZDA,060212.40,22,11,2021,-08,00*5F",
+        "$GPZDA,060212,22,11,2021,,*4E",
+        "$GPGSV,3,3,09,22,42,067,42,,,,,,,,,,,,,1*5C",
+        "$GPGGA,060220.00,3110.4706987,N,12123.2653375,E,1,5,0.8,13.543,M,8.400,M,,*5B",
+        "$GNGSA,A,3,4,5,,9,12,,,24,,,,,2.5,1.3,2.1,1*0A",
+        // 组合导航输出的 -0.00 保留负号
+        "$GPFPD,2185,108150.400,272.628,-0.000,0.188,39.9926157,116.3269623,-308580.94,-0.000,0.000,-0.000,10.191,15,18,04*72",
+        "$GTIMU,0,6.000,-0.0000,-0.0768,-3.0907,-0.1633,0.6105,0.7855,-0.0*7B",
+        "$GPCHC,2200,202733.70,258.15,-0.00,0.61,-0.03,-0.00,-0.07,0.0058,-0.0000,1.0004,31.18826868,121.60418588,16.27,-0.000,-0.002,0.002,0.011,14,17,42,0,0002*48",
+        "$GPHPD,0,0.000,0.000,-0.000,0.000,0.0000000,0.0000000,-0.00,0.000,-0.000,0.000,0.000,0,0,00*64",
+        "$GPCHC,2200,202733.70,258.15,-0.33,0.61,-0.03,0.03,-0.07,0.0058,-0.0111,1.0004,31.18826868,121.60418588,16.27,-0.011,-0.002,0.002,0.011,14,17,42,0,0002*67",
+        "$cmd,get,product,newton-m3*ff",
+    ];
+
+    for line in LINES {
+        let parsed = line[1..line.len() - 3].parse::<NmeaLine>().unwrap();
+        assert_eq!(parsed.to_string(), format!("{}\r\n", line));
+    }
+}
+
+#[test]
+fn test_lenient() {
+    const GGA: &str =
+        "GPGGA,060220.00,3959.55874779,N,11619.61828897,E,3,17,1.6,60.1397,M,-9.2862,M,,,X1,Y2";
+
+    // 多余的字段只在宽松模式下保留
+    match GGA.parse::<NmeaLine>().unwrap() {
+        NmeaLine::GGA(_, body, _) => assert_eq!(body.extra, ""),
+        _ => panic!(),
+    }
+    let line = NmeaLine::parse_with(GGA, ParseMode::Lenient).unwrap();
+    match &line {
+        NmeaLine::GGA(_, body, _) => {
+            assert_eq!(body.status, gpgga::Status::PPS定位);
+            assert_eq!(body.extra, "X1,Y2");
+        }
+        _ => panic!(),
+    }
+    assert_eq!(line.to_string(), format!("${}*42\r\n", GGA));
+
+    // 组合状态码的任一半都可以是未知的
+    let fpd = "2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,-308580.94,0.003,-0.033,-3243.491,10.191,15,18,7b";
+    assert!(fpd.parse::<gpfpd::Body>().is_err());
+    let body = gpfpd::Body::parse_with(fpd, ParseMode::Lenient).unwrap();
+    assert_eq!(
+        body.status,
+        gpfpd::Status(gpfpd::SystemStatus::差分定向, gpfpd::RtkStatus::Other(7))
+    );
+    let hpd = "0,0.000,0.000,0.000,0.000,0.0000000,0.0000000,0.00,0.000,0.000,0.000,0.000,0,0,07";
+    assert!(hpd.parse::<gphpd::Body>().is_err());
+    let body = gphpd::Body::parse_with(hpd, ParseMode::Lenient).unwrap();
+    assert_eq!(body.status, gphpd::Status::Other(7));
+    assert_eq!(body.to_string(), hpd);
+
+    // 缺省的可空尾部字段
+    let rmc = "060220.00,A,3959.55874779,N,11619.61828897,E,0.012,,170621";
+    assert_eq!(
+        rmc.parse::<gprmc::Body>(),
+        Err(BodyParseError::MissingField("GPRMC:MV"))
+    );
+    let body = gprmc::Body::parse_with(rmc, ParseMode::Lenient).unwrap();
+    assert_eq!((body.mag_var, body.mode), (None, None));
+
+    // 解析器可以设置解析模式
+    let stream = format!("${}*42\r\n", GGA);
+    let mut parser = NmeaParser::<256>::default().with_parse_mode(ParseMode::Lenient);
+    parser.as_buf()[..stream.len()].copy_from_slice(stream.as_bytes());
+    parser.notify_received(stream.len());
+    assert_eq!(parser.next().unwrap().0, line);
+}
+
+#[cfg(not(feature = "std"))]
+#[test]
+fn test_text_capacity() {
+    let tail = "0".repeat(TEXT_CAPACITY + 1);
+    assert_eq!(
+        format!("XXTXT,{}", tail).parse::<NmeaLine>(),
+        Err(BodyParseError::TooLong("Unknown:Tail"))
+    );
+    assert_eq!(
+        text_from_utf8_lossy(b"a\xffb"),
+        Text::try_from("a\u{FFFD}b").unwrap()
+    );
 }
@@ -0,0 +1,120 @@
+﻿use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// 保留原文位数的整数
+///
+/// 解析时记下包括负号在内的位数，格式化时补零到同样的位数，因此 `05`、`-08` 可以原样格式化回文本。
+/// 比较按数值进行，`05 == 5`。
+#[derive(Clone, Copy, Debug)]
+pub struct Padded<T> {
+    value: T,
+    width: u8, // 包括负号在内的最小宽度
+}
+
+impl<T> Padded<T> {
+    /// 不补零
+    #[inline]
+    pub const fn new(value: T) -> Self {
+        Self { value, width: 0 }
+    }
+
+    /// 格式化时至少补零到 `width` 位，包括负号
+    #[inline]
+    pub fn with_min_width(mut self, width: u8) -> Self {
+        self.width = self.width.max(width);
+        self
+    }
+
+    #[inline]
+    pub const fn width(&self) -> u8 {
+        self.width
+    }
+}
+
+impl<T: Copy> Padded<T> {
+    #[inline]
+    pub const fn get(&self) -> T {
+        self.value
+    }
+}
+
+impl<T> From<T> for Padded<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: FromStr> FromStr for Padded<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.parse()?;
+        // 正号格式化时不保留，不计入宽度
+        let width = s.strip_prefix('+').unwrap_or(s).len();
+        Ok(Self {
+            value,
+            width: width.try_into().unwrap_or(u8::MAX),
+        })
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Padded<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:0w$}", self.value, w = self.width as usize)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Padded<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for Padded<T> {
+    #[inline]
+    fn eq(&self, other: &T) -> bool {
+        self.value == *other
+    }
+}
+
+impl<T: Eq> Eq for Padded<T> {}
+
+impl<T: PartialOrd> PartialOrd for Padded<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Ord> Ord for Padded<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T: Hash> Hash for Padded<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+#[test]
+fn test_padded() {
+    for s in ["5", "05", "-8", "-08", "0", "000"] {
+        assert_eq!(s.parse::<Padded<i8>>().unwrap().to_string(), s);
+    }
+    assert_eq!("+08".parse::<Padded<i8>>().unwrap().to_string(), "08");
+    assert_eq!("05".parse::<Padded<u8>>().unwrap(), Padded::new(5));
+    assert_eq!("05".parse::<Padded<u8>>().unwrap(), 5);
+    assert_eq!(Padded::new(5u8).with_min_width(2).to_string(), "05");
+    assert!("".parse::<Padded<u8>>().is_err());
+}
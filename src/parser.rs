@@ -1,125 +1,615 @@
-﻿use super::NmeaLine;
-use std::str::FromStr;
+﻿#[cfg(feature = "std")]
+use super::SentenceRegistry;
+use super::{
+    raw_bytes, text_from_utf8_lossy, text_lossy, BodyParseError, NmeaLine, NmeaLineRef, ParseMode,
+    RawBytes, Text,
+};
+use core::ops::Range;
+use memchr::{memchr, memchr3};
 
+/// NMEA 语句解析器
+///
+/// 缓冲区是镜像的环形缓冲区：占用 `2 * LEN` 字节，后半是前半的镜像，
+/// 因此从前半任意位置开始、不超过 `LEN` 字节的数据总是连续的，[`NmeaParser::next_ref`] 可以直接借用缓冲区。
+/// 已解析的数据只移动读光标，未解析的数据从不挪动；
+/// 写入后半的字节在接收时复制一份到前半，每个收到的字节至多复制一次，与语句长短无关。
 pub struct NmeaParser<const LEN: usize> {
-    buf: [u8; LEN],
+    buf: [[u8; LEN]; 2],
+    head: usize, // 未解析的数据在缓冲区中的起点，总在前半
     cursor_r: Cursors,
-    cursor_w: usize,
+    cursor_w: usize, // 相对 head
 }
 
 impl<const LEN: usize> Default for NmeaParser<LEN> {
     fn default() -> Self {
         Self {
-            buf: [0u8; LEN],
-            cursor_r: Cursors { r: 0, c: 0 },
+            buf: [[0u8; LEN]; 2],
+            head: 0,
+            cursor_r: Cursors::default(),
             cursor_w: 0,
         }
     }
 }
 
-macro_rules! move_cursor {
-    ($cursor:expr, $ch:expr, $buf:ident) => {
-        loop {
-            if $cursor < $buf.len() {
-                if $buf[$cursor] == $ch {
-                    break;
-                }
-                $cursor += 1;
-            } else {
-                return false;
-            }
-        }
-    };
-}
-
 impl<const LEN: usize> NmeaParser<LEN> {
     #[inline]
-    pub fn as_buf<'a>(&'a mut self) -> &'a mut [u8] {
-        &mut self.buf[self.cursor_w..]
+    pub fn as_buf(&mut self) -> &mut [u8] {
+        self.compact();
+        &mut self.buf.as_flattened_mut()[self.head + self.cursor_w..self.head + LEN]
     }
 
     #[inline]
-    pub fn notify_received<'a>(&'a mut self, n: usize) {
+    pub fn notify_received(&mut self, n: usize) {
+        let start = self.head + self.cursor_w;
+        let end = start + n;
+        // 写到后半的字节复制到前半，读光标回绕后从前半读取；目标在 head 之前，不会覆盖未解析的数据
+        if end > LEN {
+            let from = start.max(LEN);
+            self.buf
+                .as_flattened_mut()
+                .copy_within(from..end, from - LEN);
+        }
         self.cursor_w += n;
     }
+
+    /// 设置分帧规则，默认为 [`Framing::Lenient`]
+    #[inline]
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.cursor_r.framing = framing;
+        self
+    }
+
+    /// 设置校验和规则，默认见 [`ChecksumRules::default`]
+    #[inline]
+    pub fn with_checksum(mut self, rules: ChecksumRules) -> Self {
+        self.cursor_r.checksum = rules;
+        self
+    }
+
+    /// 设置语句体的解析模式，默认为 [`ParseMode::Strict`]
+    #[inline]
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.cursor_r.mode = mode;
+        self
+    }
+
+    /// 设置注册的语句，默认为空
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn with_registry(mut self, registry: SentenceRegistry) -> Self {
+        self.cursor_r.registry = registry;
+        self
+    }
+
+    /// 以诊断模式迭代缓冲区，被拒绝的语句和丢弃的字节也会作为 [`ParseEvent`] 报告
+    #[inline]
+    pub fn diagnostics(&mut self) -> Diagnostics<'_, LEN> {
+        Diagnostics(self)
+    }
+
+    /// 从缓冲区解析一个借用缓冲区的 NEMA 消息，不分配内存
+    ///
+    /// 与 [`Iterator::next`] 相同，当且仅当缓冲区中没有完整的消息时返回 [`None`]
+    #[inline]
+    pub fn next_ref(&mut self) -> Option<ParsedSentenceRef<'_>> {
+        let buf = &self.buf.as_flattened()[self.head..self.head + self.cursor_w];
+        self.cursor_r.next_ref(buf, self.cursor_w == LEN, LEN)
+    }
+
+    /// 从缓冲区解析一个 NEMA 消息或诊断事件，当且仅当缓冲区中没有完整的消息时返回 [`None`]
+    #[inline]
+    fn parse_next(&mut self) -> Option<Result<ParsedSentence, ParseEvent>> {
+        let buf = &self.buf.as_flattened()[self.head..self.head + self.cursor_w];
+        self.cursor_r.parse_next(buf, self.cursor_w == LEN, LEN)
+    }
+
+    /// 回绕：起点移到读光标处，越过前半时回到前半的镜像；已全部解析时回到开头，减少镜像复制
+    ///
+    /// 回绕不挪动数据，但会重置光标，因此只在已全部解析或剩余空间不足 1/4 时回绕；
+    /// 解析出的语句可能借用缓冲区，因此只在准备接收时回绕
+    fn compact(&mut self) {
+        let r = self.cursor_r.r;
+        if r == 0 || (r < self.cursor_w && LEN - self.cursor_w > LEN / 4) {
+            return;
+        }
+        if r == self.cursor_w {
+            self.head = 0;
+        } else {
+            self.head += r;
+            if self.head >= LEN {
+                self.head -= LEN;
+            }
+        }
+        self.cursor_w -= r;
+        self.cursor_r.reset();
+    }
 }
 
 impl<const LEN: usize> Iterator for NmeaParser<LEN> {
     type Item = (NmeaLine, u8);
 
     /// 从缓冲区解析一个 NEMA 消息，当且仅当缓冲区中没有完整的消息时返回 [`None`]，此时需要读取新的数据填充到缓冲区
+    ///
+    /// 为兼容保留的有损接口：只产出语句和一个校验和（语句携带的，没有携带时是计算出的），
+    /// 原始文本、偏移量和是否验证过都会丢失，被拒绝的语句和丢弃的字节被静默跳过，没有 `std` 时超过文本容量的语句也被跳过。
+    /// 新代码使用产出 [`ParsedSentence`] 和 [`ParseEvent`] 的 [`NmeaParser::diagnostics`]（有 `std` 时保留完整的原始字节），
+    /// 或不分配内存的 [`NmeaParser::next_ref`]
     fn next(&mut self) -> Option<Self::Item> {
-        let result = loop {
-            // 无法继续解析或全部解析完成
-            if !self.cursor_r.move_on(&self.buf[..self.cursor_w])
-                || self.cursor_w < self.cursor_r.c + 3
-            {
-                // 缓冲区全满，从头丢弃 1 字节
-                if self.cursor_r.r == 0 && self.cursor_w == LEN {
-                    self.cursor_r.r = 1;
-                    continue;
-                }
-                // 放弃继续解析，准备接收
-                else {
-                    break None;
-                }
+        loop {
+            let sentence = self.next_ref()?;
+            let checksum = sentence.checksum();
+            if let Ok(line) = sentence.line.try_into_owned() {
+                break Some((line, checksum));
             }
-            if self.cursor_r.len() >= 3 && self.buf[self.cursor_r.r + 1..].starts_with(b"cmd") {
-                // $cmd...*ff
-                if self.buf[self.cursor_r.c + 1..].starts_with(b"ff") {
-                    break self.cursor_r.complete(&self.buf[..self.cursor_w]);
-                }
-            } else if self.cursor_r.xor_check(&self.buf[..self.cursor_w]) {
-                break self.cursor_r.complete(&self.buf[..self.cursor_w]);
+        }
+    }
+}
+
+/// 解析成功的 NMEA 语句
+///
+/// 没有 `std` 时，`raw` 超过 [`TEXT_CAPACITY`](crate::TEXT_CAPACITY) 的部分被截断，完整的原文见 [`NmeaParser::next_ref`]
+#[derive(Debug, PartialEq)]
+pub struct ParsedSentence<L = NmeaLine, R = RawBytes> {
+    pub line: L,
+    /// 从 `$` 到校验和（没有校验和时到行尾之前）的原始字节
+    pub raw: R,
+    /// `$` 在输入流中的字节偏移量，从解析器创建时算起
+    pub offset: u64,
+    /// 语句携带的校验和，没有 `*` 或不是合法的十六进制时为 [`None`]
+    pub received: Option<u8>,
+    /// 计算出的异或校验和
+    pub computed: u8,
+    /// 是否按异或校验和验证通过，[`ChecksumPolicy::Fixed`] 和 [`ChecksumPolicy::Ignored`] 总是 `false`
+    pub verified: bool,
+}
+
+/// 借用解析器缓冲区的 [`ParsedSentence`]，见 [`NmeaParser::next_ref`]
+pub type ParsedSentenceRef<'a> = ParsedSentence<NmeaLineRef<'a>, &'a [u8]>;
+
+impl<L, R> ParsedSentence<L, R> {
+    /// 语句携带的校验和，没有携带时为计算出的
+    #[inline]
+    pub fn checksum(&self) -> u8 {
+        self.received.unwrap_or(self.computed)
+    }
+}
+
+/// 诊断模式下报告的解析事件
+///
+/// 没有 `std` 时，保留的原始文本和字节超过 [`TEXT_CAPACITY`](crate::TEXT_CAPACITY) 的部分被截断
+#[derive(Debug, PartialEq)]
+pub enum ParseEvent {
+    /// 校验和不匹配或缺失，`raw` 是从 `$` 到校验和（或行尾）的原始文本
+    ChecksumMismatch {
+        raw: Text,
+        expected: u8,
+        received: Option<u8>,
+    },
+    /// 校验通过但语句体解析失败，`raw` 是去掉 `$` 和校验和的原始文本
+    BodyParseFailed { error: BodyParseError, raw: Text },
+    /// 同步到下一个 `$` 时丢弃的字节
+    Discarded(RawBytes),
+    /// 超过最大长度仍未结束的语句，从 `$` 起的 `len` 字节被丢弃
+    Oversized { len: usize },
+    /// 长度超过软上限的语句，`$` 在 `offset` 处，到校验和共 `len` 字节；只由 `NmeaVecParser` 报告，该语句随后照常产出
+    SoftLimitExceeded { offset: u64, len: usize },
+    /// 分帧不合规的语句，`raw` 是从 `$` 起被丢弃的原始文本
+    BadFrame { raw: Text, error: FrameError },
+}
+
+/// 语句分帧不合规的原因，除 [`FrameError::InvalidUtf8`] 外只在严格分帧模式下报告
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameError {
+    /// 行尾之前出现了新的 `$`，前半条语句被截断
+    Interrupted,
+    /// 没有以 `*HH\r\n` 结尾
+    MissingTerminator,
+    /// 包括 `$` 和行尾超过了最大长度
+    TooLong,
+    /// 语句体含有不可打印的字符
+    NonPrintable,
+    /// 语句体不是合法的 UTF-8
+    InvalidUtf8,
+}
+
+/// 分帧规则
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Framing {
+    /// 只按 `$` 和 `*HH` 分帧，忽略行尾，兼容不规范的设备
+    #[default]
+    Lenient,
+    /// 按 NMEA 0183 严格分帧，`max_len` 包括 `$` 和行尾
+    Strict { max_len: usize },
+}
+
+impl Framing {
+    /// NMEA 0183 规定的语句最大长度
+    pub const NMEA0183_MAX_LEN: usize = 82;
+
+    /// 最大长度为 82 的严格分帧
+    #[inline]
+    pub const fn strict() -> Self {
+        Self::Strict {
+            max_len: Self::NMEA0183_MAX_LEN,
+        }
+    }
+}
+
+/// 校验和规则
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChecksumPolicy {
+    /// 必须携带正确的异或校验和
+    #[default]
+    Required,
+    /// 可以不带校验和，携带时必须正确
+    Optional,
+    /// 必须携带固定的值，如 `$cmd...*ff`
+    Fixed(u8),
+    /// 不检查，带不带都可以
+    Ignored,
+}
+
+/// 按语句头设置的校验和规则
+///
+/// 语句头是 `$` 与第一个 `,` 之间的部分，如 `GPGGA`、`cmd`，没有单独设置的语句头使用默认规则。
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChecksumRules {
+    default: ChecksumPolicy,
+    heads: heapless::Vec<(&'static str, ChecksumPolicy), { ChecksumRules::CAPACITY }>,
+}
+
+impl Default for ChecksumRules {
+    /// 默认要求正确的异或校验和，`cmd` 必须以 `*ff` 结尾
+    fn default() -> Self {
+        Self::new(ChecksumPolicy::Required).with("cmd", ChecksumPolicy::Fixed(0xff))
+    }
+}
+
+impl ChecksumRules {
+    /// 最多可以单独设置的语句头数量
+    pub const CAPACITY: usize = 8;
+
+    /// 所有语句使用同一规则
+    #[inline]
+    pub fn new(default: ChecksumPolicy) -> Self {
+        Self {
+            default,
+            heads: heapless::Vec::new(),
+        }
+    }
+
+    /// 设置一种语句头的规则，覆盖之前的设置
+    ///
+    /// # Panics
+    ///
+    /// 单独设置的语句头超过 [`ChecksumRules::CAPACITY`] 种
+    pub fn with(mut self, head: &'static str, policy: ChecksumPolicy) -> Self {
+        match self.heads.iter_mut().find(|(h, _)| *h == head) {
+            Some((_, p)) => *p = policy,
+            None => self
+                .heads
+                .push((head, policy))
+                .expect("too many checksum rules"),
+        }
+        self
+    }
+
+    /// 设置没有单独设置的语句头使用的规则
+    #[inline]
+    pub fn with_default(mut self, policy: ChecksumPolicy) -> Self {
+        self.default = policy;
+        self
+    }
+
+    /// 语句头对应的规则
+    pub fn policy(&self, head: &[u8]) -> ChecksumPolicy {
+        self.heads
+            .iter()
+            .find(|(h, _)| h.as_bytes() == head)
+            .map_or(self.default, |(_, p)| *p)
+    }
+
+    /// 检查 `$` 与 `*` 之间的 `body`，`received` 是语句携带的校验和，没有 `*` 时为 [`None`]
+    ///
+    /// 通过时返回计算出的异或校验和及是否验证过，否则返回期望的和收到的校验和
+    pub(crate) fn check(
+        &self,
+        body: &[u8],
+        received: Option<Option<u8>>,
+    ) -> Result<(u8, bool), (u8, Option<u8>)> {
+        let head = body.split(|b| *b == b',').next().unwrap_or(body);
+        let xor = checksum(body);
+        match (self.policy(head), received) {
+            (ChecksumPolicy::Required | ChecksumPolicy::Optional, Some(cs)) if cs == Some(xor) => {
+                Ok((xor, true))
             }
-            self.cursor_r.move_next();
-        };
-        // 尽量挪动内存以尽量多从外设读取
-        if (1..self.cursor_w).contains(&self.cursor_r.r) {
-            self.buf.copy_within(self.cursor_r.r..self.cursor_w, 0);
+            (ChecksumPolicy::Optional | ChecksumPolicy::Ignored, None) => Ok((xor, false)),
+            (ChecksumPolicy::Fixed(fixed), Some(Some(cs))) if cs == fixed => Ok((xor, false)),
+            (ChecksumPolicy::Fixed(fixed), received) => Err((fixed, received.flatten())),
+            (ChecksumPolicy::Ignored, Some(_)) => Ok((xor, false)),
+            (_, received) => Err((xor, received.flatten())),
         }
-        self.cursor_w -= self.cursor_r.r;
-        self.cursor_r.reset();
-        return result;
     }
 }
 
-struct Cursors {
-    r: usize, // 光标：读取，移动缓冲字节时要保留的第一个字节
-    c: usize, // 光标：检查，扫描的起点
+/// 诊断模式的迭代器，见 [`NmeaParser::diagnostics`]
+pub struct Diagnostics<'a, const LEN: usize>(&'a mut NmeaParser<LEN>);
+
+impl<const LEN: usize> Iterator for Diagnostics<'_, LEN> {
+    type Item = Result<ParsedSentence, ParseEvent>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.parse_next()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cursors {
+    pub r: usize,                // 光标：读取，移动缓冲字节时要保留的第一个字节
+    c: usize,                    // 光标：检查，扫描的起点
+    pub framing: Framing,        // 分帧规则
+    pub checksum: ChecksumRules, // 校验和规则
+    pub mode: ParseMode,         // 语句体的解析模式
+    #[cfg(feature = "std")]
+    pub registry: SentenceRegistry, // 注册的语句
+    base: u64,                   // 缓冲区开头在输入流中的偏移量
+}
+
+/// 找到的一帧：原始语句和 `$` 与 `*`（或行尾）之间的范围，以及校验结果
+struct Frame {
+    raw: Range<usize>,
+    body: Range<usize>,
+    received: Option<u8>,
+    computed: u8,
+    verified: bool,
+}
+
+impl Frame {
+    #[inline]
+    fn sentence<L, R>(&self, line: L, raw: R, base: u64) -> ParsedSentence<L, R> {
+        ParsedSentence {
+            line,
+            raw,
+            offset: base + self.raw.start as u64,
+            received: self.received,
+            computed: self.computed,
+            verified: self.verified,
+        }
+    }
 }
 
 impl Cursors {
     /// r 移动到 '$'
-    /// c 移动到 '*'
+    /// c 移动到 '*' 或行尾
     fn move_on(&mut self, buf: &[u8]) -> bool {
-        move_cursor!(self.r, b'$', buf);
+        match memchr(b'$', &buf[self.r..]) {
+            Some(i) => self.r += i,
+            None => {
+                self.r = buf.len();
+                return false;
+            }
+        }
         if self.r >= self.c {
             self.c = self.r + 1;
         }
-        move_cursor!(self.c, b'*', buf);
-        true
+        match memchr3(b'*', b'\r', b'\n', &buf[self.c..]) {
+            Some(i) => {
+                self.c += i;
+                true
+            }
+            None => {
+                self.c = buf.len();
+                false
+            }
+        }
     }
 
-    /// 从 buf 切出字符串
-    fn complete(&mut self, buf: &[u8]) -> Option<(NmeaLine, u8)> {
-        let cs = parse_cs(&buf[self.c..])?;
-        let result = unsafe { std::str::from_utf8_unchecked(&buf[self.r + 1..self.c]) };
-        self.move_next();
-        self.move_on(buf);
-        Some((NmeaLine::from_str(result).ok()?, cs))
+    /// 按解析模式解析语句，先查找注册的语句
+    #[cfg(feature = "std")]
+    #[inline]
+    fn parse_line<'a>(&self, body: &'a str) -> Result<NmeaLineRef<'a>, BodyParseError> {
+        self.registry.parse(body, self.mode)
     }
 
-    /// 一次解析完成
+    /// 按解析模式解析语句
+    #[cfg(not(feature = "std"))]
     #[inline]
-    fn move_next(&mut self) {
-        self.r = self.c + 3;
-        self.c += 4;
+    fn parse_line<'a>(&self, body: &'a str) -> Result<NmeaLineRef<'a>, BodyParseError> {
+        NmeaLineRef::parse_with(body, self.mode)
+    }
+
+    /// 从 `buf` 中解析一个借用的语句，跳过所有诊断事件
+    pub fn next_ref<'a>(
+        &mut self,
+        buf: &'a [u8],
+        full: bool,
+        max_len: usize,
+    ) -> Option<ParsedSentenceRef<'a>> {
+        loop {
+            if let Ok(frame) = self.next_frame(buf, full, max_len)? {
+                let Ok(body) = core::str::from_utf8(&buf[frame.body.clone()]) else {
+                    continue;
+                };
+                if let Ok(line) = self.parse_line(body) {
+                    break Some(frame.sentence(line, &buf[frame.raw.clone()], self.base));
+                }
+            }
+        }
     }
 
-    /// 回车
+    /// 从 `buf` 中解析一个语句或诊断事件
+    pub fn parse_next(
+        &mut self,
+        buf: &[u8],
+        full: bool,
+        max_len: usize,
+    ) -> Option<Result<ParsedSentence, ParseEvent>> {
+        let frame = match self.next_frame(buf, full, max_len)? {
+            Ok(frame) => frame,
+            Err(event) => return Some(Err(event)),
+        };
+        let Ok(body) = core::str::from_utf8(&buf[frame.body.clone()]) else {
+            let raw = &buf[frame.raw.start..frame.body.end];
+            return Some(Err(bad_frame(raw, FrameError::InvalidUtf8)));
+        };
+        Some(
+            match self.parse_line(body).and_then(NmeaLineRef::try_into_owned) {
+                Ok(line) => Ok(frame.sentence(line, raw_bytes(&buf[frame.raw.clone()]), self.base)),
+                Err(error) => Err(ParseEvent::BodyParseFailed {
+                    error,
+                    raw: text_lossy(body),
+                }),
+            },
+        )
+    }
+
+    /// 找到下一个完整的语句并按规则校验
+    ///
+    /// 宽松模式下，`*` 之前遇到行尾的语句视为没有校验和；
+    /// `full` 表示缓冲区已满，此时无法同步就从头丢弃 1 字节；
+    /// 从 `$` 起超过 `max_len` 字节仍未结束的语句被丢弃
+    fn next_frame(
+        &mut self,
+        buf: &[u8],
+        full: bool,
+        max_len: usize,
+    ) -> Option<Result<Frame, ParseEvent>> {
+        if let Framing::Strict { max_len: limit } = self.framing {
+            return self.next_strict_frame(buf, full, max_len.min(limit));
+        }
+        let start = self.r;
+        loop {
+            // 无法继续解析或全部解析完成
+            let ready = self.move_on(buf) && (buf[self.c] != b'*' || buf.len() >= self.c + 3);
+            // 同步过程中跳过的字节，行尾的空白不算
+            let skipped = &buf[start..self.r];
+            if !skipped.iter().all(u8::is_ascii_whitespace) {
+                return Some(Err(ParseEvent::Discarded(raw_bytes(skipped))));
+            }
+            // 超长的语句，同步到下一个 `$`
+            if self.r < buf.len() && self.c - self.r > max_len {
+                let head = self.r;
+                self.r = memchr(b'$', &buf[head + 1..]).map_or(buf.len(), |i| head + 1 + i);
+                return Some(Err(ParseEvent::Oversized { len: self.r - head }));
+            }
+            if ready {
+                break;
+            }
+            // 缓冲区全满，从头丢弃 1 字节
+            if self.r == 0 && full {
+                self.r = 1;
+            }
+            // 放弃继续解析，准备接收
+            else {
+                return None;
+            }
+        }
+        let (r, c) = (self.r, self.c);
+        self.move_next(buf[c] == b'*');
+        Some(self.verify(buf, r, c))
+    }
+
+    /// 严格分帧：语句必须以 `*HH\r\n` 结尾（规则允许时也可以只有 `\r\n`），不能超过 `max_len` 字节，且只含可打印字符
+    fn next_strict_frame(
+        &mut self,
+        buf: &[u8],
+        full: bool,
+        max_len: usize,
+    ) -> Option<Result<Frame, ParseEvent>> {
+        let start = self.r;
+        // 同步到 '$'
+        self.r += memchr(b'$', &buf[start..]).unwrap_or(buf.len() - start);
+        let skipped = &buf[start..self.r];
+        if !skipped.iter().all(u8::is_ascii_whitespace) {
+            return Some(Err(ParseEvent::Discarded(raw_bytes(skipped))));
+        }
+        let r = self.r;
+        if r == buf.len() {
+            return None;
+        }
+        // 找行尾，c 是扫描的起点
+        if self.c <= r {
+            self.c = r + 1;
+        }
+        let n = memchr(b'\n', &buf[self.c..]).map(|i| self.c + i);
+        let end = n.map_or(buf.len(), |n| n + 1);
+        // 行内出现新的 '$'，前半条语句被截断
+        if let Some(i) = memchr(b'$', &buf[r + 1..end]) {
+            self.r = r + 1 + i;
+            return Some(Err(bad_frame(&buf[r..self.r], FrameError::Interrupted)));
+        }
+        let n = match n {
+            Some(n) => n,
+            // 没有行尾，且不可能再等到
+            None if end - r > max_len || (full && r == 0) => {
+                self.r = end;
+                return Some(Err(bad_frame(&buf[r..end], FrameError::TooLong)));
+            }
+            None => {
+                self.c = end;
+                return None;
+            }
+        };
+        self.r = n + 1;
+        let line = &buf[r..=n];
+        if line.len() > max_len {
+            return Some(Err(bad_frame(line, FrameError::TooLong)));
+        }
+        if !line.ends_with(b"\r\n") {
+            return Some(Err(bad_frame(line, FrameError::MissingTerminator)));
+        }
+        // '*' 或没有校验和时的 '\r'
+        let star = if line.len() >= 6 && line[line.len() - 5] == b'*' {
+            n - 4
+        } else if !line.contains(&b'*') {
+            n - 1
+        } else {
+            return Some(Err(bad_frame(line, FrameError::MissingTerminator)));
+        };
+        if !buf[r + 1..star].iter().all(|b| (b' '..=b'~').contains(b)) {
+            return Some(Err(bad_frame(line, FrameError::NonPrintable)));
+        }
+        Some(self.verify(buf, r, star))
+    }
+
+    /// 一次解析完成，`star` 表示 c 在 '*' 上，否则在行尾
     #[inline]
-    fn reset(&mut self) {
+    fn move_next(&mut self, star: bool) {
+        if star {
+            self.r = self.c + 3;
+            self.c += 4;
+        } else {
+            self.r = self.c;
+            self.c += 1;
+        }
+    }
+
+    /// 按规则校验 `$` 在 `r`、`*` 或行尾在 `c` 的语句
+    fn verify(&self, buf: &[u8], r: usize, c: usize) -> Result<Frame, ParseEvent> {
+        let star = buf[c] == b'*';
+        let raw = r..if star { c + 3 } else { c };
+        let received = star.then(|| parse_cs(&buf[c..]));
+        match self.checksum.check(&buf[r + 1..c], received) {
+            Ok((computed, verified)) => Ok(Frame {
+                raw,
+                body: r + 1..c,
+                received: received.flatten(),
+                computed,
+                verified,
+            }),
+            Err((expected, received)) => Err(ParseEvent::ChecksumMismatch {
+                raw: text_from_utf8_lossy(&buf[raw]),
+                expected,
+                received,
+            }),
+        }
+    }
+
+    /// 回车，缓冲区中 r 之前的字节被移走
+    #[inline]
+    pub fn reset(&mut self) {
+        self.base += self.r as u64;
         if self.c > self.r {
             self.c -= self.r;
         } else {
@@ -127,19 +617,20 @@ impl Cursors {
         }
         self.r = 0;
     }
+}
 
-    /// 异或校验
-    #[inline]
-    fn xor_check<'a>(&self, buf: &'a [u8]) -> bool {
-        Some(buf[self.r + 1..self.c].iter().fold(0, |sum, it| sum ^ *it))
-            == parse_cs(&buf[self.c..])
+#[inline]
+fn bad_frame(raw: &[u8], error: FrameError) -> ParseEvent {
+    ParseEvent::BadFrame {
+        raw: text_from_utf8_lossy(raw),
+        error,
     }
+}
 
-    /// 已检查的区段长度
-    #[inline]
-    fn len(&self) -> usize {
-        self.c - self.r - 1
-    }
+/// 异或校验和，`body` 是 `$` 与 `*` 之间的部分
+#[inline]
+fn checksum(body: &[u8]) -> u8 {
+    body.iter().fold(0, |sum, it| sum ^ *it)
 }
 
 #[inline]
@@ -152,7 +643,348 @@ fn parse_u8(byte: u8) -> Option<u8> {
     }
 }
 
+/// 解析 `*HH`，不足 3 字节时返回 [`None`]
 #[inline]
-fn parse_cs(cs: &[u8]) -> Option<u8> {
-    Some(parse_u8(cs[1])? << 4 | parse_u8(cs[2])?)
+pub(crate) fn parse_cs(cs: &[u8]) -> Option<u8> {
+    match cs {
+        [_, h, l, ..] => Some(parse_u8(*h)? << 4 | parse_u8(*l)?),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_diagnostics() {
+    const STREAM: &[u8] =
+        b"xx$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n\
+$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4D\r\n\
+$GTIMU,0,6.000*5A\r\n";
+
+    let mut parser = NmeaParser::<256>::default();
+    parser.as_buf()[..STREAM.len()].copy_from_slice(STREAM);
+    parser.notify_received(STREAM.len());
+
+    let mut diagnostics = parser.diagnostics();
+    assert_eq!(
+        diagnostics.next(),
+        Some(Err(ParseEvent::Discarded(raw_bytes(b"xx"))))
+    );
+    assert!(matches!(
+        diagnostics.next(),
+        Some(Ok(ParsedSentence {
+            line: NmeaLine::GTIMU(_),
+            offset: 2,
+            received: Some(0x4C),
+            computed: 0x4C,
+            verified: true,
+            ..
+        }))
+    ));
+    assert!(matches!(
+        diagnostics.next(),
+        Some(Err(ParseEvent::ChecksumMismatch {
+            expected: 0x4C,
+            received: Some(0x4D),
+            ..
+        }))
+    ));
+    assert_eq!(
+        diagnostics.next(),
+        Some(Err(ParseEvent::BodyParseFailed {
+            error: BodyParseError::MissingField("GTIMU:GyroX"),
+            raw: text_lossy("GTIMU,0,6.000"),
+        }))
+    );
+    assert_eq!(diagnostics.next(), None);
+
+    // 有 `std` 时保留完整的原始字节
+    #[cfg(feature = "std")]
+    {
+        const CHC: &[u8] = b"$GPCHC,2200,202733.70,258.15,-0.33,0.61,-0.03,0.03,-0.07,0.0058,-0.0111,1.0004,31.18826868,121.60418588,16.27,-0.011,-0.002,0.002,0.011,14,17,42,0,0002*67";
+        let mut parser = NmeaParser::<256>::default();
+        parser.as_buf()[..CHC.len() + 2].copy_from_slice(&[CHC, b"\r\n"].concat());
+        parser.notify_received(CHC.len() + 2);
+        let sentence = parser.diagnostics().next().unwrap().unwrap();
+        assert!(CHC.len() > crate::TEXT_CAPACITY);
+        assert_eq!(sentence.raw, CHC);
+    }
+}
+
+#[test]
+fn test_ring_buffer() {
+    const LINES: [&[u8]; 2] = [
+        b"$GPCHC,2200,202733.70,258.15,-0.33,0.61,-0.03,0.03,-0.07,0.0058,-0.0111,1.0004,31.18826868,121.60418588,16.27,-0.011,-0.002,0.002,0.011,14,17,42,0,0002*67\r\n",
+        b"$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n",
+    ];
+
+    // 长语句几乎占满缓冲区，分块到达时反复回绕，借用的原文仍然连续
+    let stream = LINES.iter().cycle().take(100).copied().collect::<Vec<_>>();
+    let mut data = &stream.concat()[..];
+    let mut parser = NmeaParser::<160>::default();
+    let mut raws = Vec::new();
+    while !data.is_empty() {
+        let buf = parser.as_buf();
+        let n = buf.len().min(data.len()).min(37);
+        buf[..n].copy_from_slice(&data[..n]);
+        parser.notify_received(n);
+        data = &data[n..];
+        while let Some(sentence) = parser.next_ref() {
+            raws.push(sentence.raw.to_vec());
+        }
+    }
+    assert_eq!(raws.len(), stream.len());
+    for (raw, line) in raws.iter().zip(stream) {
+        assert_eq!(raw[..], line[..line.len() - 2]);
+    }
+}
+
+#[test]
+fn test_next_ref() {
+    const STREAM: &[u8] =
+        b"$GPGGA,060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42\r\n\
+$cmd,get,product,newton-m3*ff\r\n\
+$GPGGA,060220.00,3959.55";
+
+    let mut parser = NmeaParser::<160>::default();
+    parser.as_buf()[..STREAM.len()].copy_from_slice(STREAM);
+    parser.notify_received(STREAM.len());
+
+    let ParsedSentence {
+        line,
+        raw,
+        offset,
+        received,
+        ..
+    } = parser.next_ref().unwrap();
+    assert_eq!((offset, received), (0, Some(0x42)));
+    assert_eq!(raw, &STREAM[..83]);
+    let owned = line.try_to_owned().unwrap();
+    match line {
+        NmeaLineRef::GGA(_, _, tail) => assert!(tail.starts_with("060220.00,3959.55874779")),
+        _ => panic!(),
+    }
+    assert_eq!(owned.to_string(), line.to_string());
+    #[cfg(feature = "std")]
+    assert_eq!(line.to_owned(), owned);
+
+    let cmd = parser.next_ref().unwrap();
+    assert_eq!(
+        cmd.line,
+        NmeaLineRef::CMD(crate::cmd::BodyRef::Get("product,newton-m3"))
+    );
+    assert_eq!(
+        (cmd.offset, cmd.raw),
+        (85, &b"$cmd,get,product,newton-m3*ff"[..])
+    );
+    assert_eq!((cmd.received, cmd.checksum()), (Some(0xff), 0xff));
+    assert!(parser.next_ref().is_none());
+
+    // 准备接收时才挪动内存，剩下的半条语句可以接上
+    const REST: &[u8] = b"874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42\r\n";
+    parser.as_buf()[..REST.len()].copy_from_slice(REST);
+    parser.notify_received(REST.len());
+    let sentence = parser.diagnostics().next().unwrap().unwrap();
+    assert_eq!(sentence.line, owned);
+    // 挪动过缓冲区，偏移量仍从流的开头算起
+    assert_eq!(sentence.offset, 116);
+    assert_eq!(
+        sentence.raw,
+        raw_bytes(&[&STREAM[116..], REST].concat()[..83])
+    );
+    assert!(parser.next_ref().is_none());
+}
+
+#[test]
+fn test_strict_framing() {
+    const TIMU: &[u8] = b"$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C";
+    const FPD: &[u8] = b"$GPFPD,2185,108150.400,272.628,2.722,0.188,39.9926157,116.3269623,-308580.94,0.003,-0.033,-3243.491,10.191,15,18,04*63\r\n";
+
+    fn events(framing: Framing, parts: &[&[u8]]) -> Vec<Result<ParsedSentence, ParseEvent>> {
+        let mut parser = NmeaParser::<256>::default().with_framing(framing);
+        let stream = parts.concat();
+        parser.as_buf()[..stream.len()].copy_from_slice(&stream);
+        parser.notify_received(stream.len());
+        parser.diagnostics().collect()
+    }
+    fn error(event: &Result<ParsedSentence, ParseEvent>) -> Option<FrameError> {
+        match event {
+            Err(ParseEvent::BadFrame { error, .. }) => Some(*error),
+            _ => None,
+        }
+    }
+
+    // 半条语句粘上了下一条
+    let glued: &[&[u8]] = &[b"$GPGGA,0602", TIMU, b"\r\n"];
+    let strict = events(Framing::strict(), glued);
+    assert_eq!(strict.len(), 2);
+    assert_eq!(error(&strict[0]), Some(FrameError::Interrupted));
+    assert!(strict[1].is_ok());
+    // 宽松模式把两半当作一条，校验失败
+    let lenient = events(Framing::Lenient, glued);
+    assert!(matches!(
+        lenient[..],
+        [Err(ParseEvent::ChecksumMismatch { .. })]
+    ));
+
+    // 行中间的 *HH
+    let strict = events(Framing::strict(), &[TIMU, b" garbage\r\n", TIMU, b"\r\n"]);
+    assert_eq!(error(&strict[0]), Some(FrameError::MissingTerminator));
+    assert!(strict[1].is_ok());
+    // 没有行尾就开始了下一条
+    let strict = events(Framing::strict(), &[TIMU, TIMU, b"\r\n"]);
+    assert_eq!(error(&strict[0]), Some(FrameError::Interrupted));
+    assert!(strict[1].is_ok());
+    // 不可打印字符
+    let strict = events(Framing::strict(), &[b"$GPXXX,a\x01b*61\r\n"]);
+    assert_eq!(error(&strict[0]), Some(FrameError::NonPrintable));
+    assert!(events(Framing::Lenient, &[b"$GPXXX,a\x01b*61\r\n"])[0].is_ok());
+    // 长度限制可以配置
+    let strict = events(Framing::strict(), &[FPD]);
+    assert_eq!(error(&strict[0]), Some(FrameError::TooLong));
+    assert!(events(Framing::Strict { max_len: 128 }, &[FPD])[0].is_ok());
+    // 行尾还没收到
+    assert!(events(Framing::strict(), &[TIMU]).is_empty());
+}
+
+#[test]
+fn test_checksum_policy() {
+    fn events(
+        rules: ChecksumRules,
+        framing: Framing,
+        stream: &[u8],
+    ) -> Vec<Result<(bool, u8), u8>> {
+        let mut parser = NmeaParser::<256>::default()
+            .with_framing(framing)
+            .with_checksum(rules);
+        parser.as_buf()[..stream.len()].copy_from_slice(stream);
+        parser.notify_received(stream.len());
+        parser
+            .diagnostics()
+            .map(|event| match event {
+                Ok(sentence) => Ok((sentence.verified, sentence.checksum())),
+                Err(ParseEvent::ChecksumMismatch { expected, .. }) => Err(expected),
+                Err(e) => panic!("{e:?}"),
+            })
+            .collect()
+    }
+
+    const STREAM: &[u8] =
+        b"$PXYZ,1,2\r\n$cmd,get,product*ff\r\n$cmd,get,product*77\r\n$GPXXX,a*02\r\n";
+    let lenient = Framing::Lenient;
+    // 默认：没有校验和的语句被拒绝，cmd 必须是 *ff
+    assert_eq!(
+        events(ChecksumRules::default(), lenient, STREAM),
+        [Err(0x08), Ok((false, 0xff)), Err(0xff), Ok((true, 0x02))]
+    );
+    // 按语句头配置
+    let rules = ChecksumRules::default()
+        .with("PXYZ", ChecksumPolicy::Optional)
+        .with("cmd", ChecksumPolicy::Required);
+    assert_eq!(
+        events(rules.clone(), lenient, STREAM),
+        [
+            Ok((false, 0x08)),
+            Err(0x77),
+            Ok((true, 0x77)),
+            Ok((true, 0x02))
+        ]
+    );
+    assert_eq!(
+        events(rules, Framing::strict(), STREAM),
+        [
+            Ok((false, 0x08)),
+            Err(0x77),
+            Ok((true, 0x77)),
+            Ok((true, 0x02))
+        ]
+    );
+    // 携带时仍要正确
+    let optional = ChecksumRules::new(ChecksumPolicy::Optional);
+    assert_eq!(events(optional, lenient, b"$GPXXX,a*03\r\n"), [Err(0x02)]);
+    // 完全不检查，报告收到的校验和
+    let ignored = ChecksumRules::new(ChecksumPolicy::Ignored);
+    assert_eq!(
+        events(ignored, lenient, b"$GPXXX,a*03\r\n$PXYZ,1,2\r\n"),
+        [Ok((false, 0x03)), Ok((false, 0x08))]
+    );
+}
+
+#[cfg(test)]
+mod prop {
+    use super::*;
+    use proptest::{collection::vec, prelude::*};
+
+    /// 偏向 NMEA 分隔符的任意字节
+    fn nmea_byte() -> impl Strategy<Value = u8> {
+        prop_oneof![
+            any::<u8>(),
+            proptest::sample::select(b"$*,.\r\n0123456789ABCDEFGPNSEW-".as_slice()),
+        ]
+    }
+
+    /// 形如语句体的文本：已知或随机的语句头，加上形如数字和标志的字段
+    fn body() -> impl Strategy<Value = String> {
+        (
+            "(GP|GN|BD|GL|GA)?(FPD|GGA|RMC|GSA|GSV|ZDA|CHC|HPD)|GTIMU|cmd|[A-Za-z]{0,6}",
+            vec(
+                "[-+]?[0-9]{0,48}(\\.[0-9]{0,48})?|[NSEWAVMDTKnh]|[a-z,]{0,8}|",
+                0..32,
+            ),
+        )
+            .prop_map(|(head, fields)| {
+                [head]
+                    .into_iter()
+                    .chain(fields)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+    }
+
+    proptest! {
+        /// 任意字节以任意分块送入解析器都不会 panic
+        #[test]
+        fn parser_never_panics(
+            stream in vec(nmea_byte(), 0..2048),
+            chunk in 1usize..64,
+            strict in any::<bool>(),
+        ) {
+            let framing = if strict { Framing::strict() } else { Framing::Lenient };
+            let rules = ChecksumRules::new(ChecksumPolicy::Ignored);
+            let mut parser = NmeaParser::<128>::default().with_framing(framing).with_checksum(rules);
+            for mut chunk in stream.chunks(chunk) {
+                while !chunk.is_empty() {
+                    for sentence in parser.diagnostics().flatten() {
+                        let _ = sentence.line.to_string();
+                    }
+                    let buf = parser.as_buf();
+                    let n = buf.len().min(chunk.len());
+                    buf[..n].copy_from_slice(&chunk[..n]);
+                    parser.notify_received(n);
+                    chunk = &chunk[n..];
+                }
+                while let Some(sentence) = parser.next_ref() {
+                    let _ = sentence.line.to_string();
+                }
+            }
+        }
+
+        /// 带正确校验和的语句从解析到重新编码都不会 panic
+        #[test]
+        fn line_never_panics(body in body()) {
+            let cs = checksum(body.as_bytes());
+            let mut parser = NmeaParser::<256>::default();
+            let sentence = format!("${body}*{cs:02X}\r\n");
+            let n = sentence.len().min(256);
+            parser.as_buf()[..n].copy_from_slice(&sentence.as_bytes()[..n]);
+            parser.notify_received(n);
+            for sentence in parser.diagnostics().flatten() {
+                let _ = sentence.line.to_string();
+                prop_assert!(sentence.line == sentence.line);
+            }
+            for mode in [ParseMode::Strict, ParseMode::Lenient] {
+                if let Ok(line) = NmeaLineRef::parse_with(&body, mode) {
+                    let _ = line.to_string();
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,353 @@
+﻿use super::{Lla, Position, WGS84_A, WGS84_F};
+
+/// 参考椭球
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ellipsoid {
+    pub a: f64, // 长半轴 / m
+    pub f: f64, // 扁率
+}
+
+pub const WGS84: Ellipsoid = Ellipsoid {
+    a: WGS84_A,
+    f: WGS84_F,
+};
+
+pub const CGCS2000: Ellipsoid = Ellipsoid {
+    a: 6378137.0,
+    f: 1.0 / 298.257222101,
+};
+
+/// 分带方式
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Zoning {
+    Utm, // UTM 6° 带，中央子午线 6n-183
+    Gk6, // 高斯-克吕格 6° 带，中央子午线 6n-3
+    Gk3, // 高斯-克吕格 3° 带，中央子午线 3n
+}
+
+/// 投影坐标
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Grid {
+    pub zone: u8,         // 带号
+    pub south: bool,      // UTM 南半球，北坐标加了 10000 km
+    pub easting: f64,     // 东坐标 / m，包括东伪偏移和带号前缀
+    pub northing: f64,    // 北坐标 / m
+    pub altitude: f64,    // 椭球高 / m，原样保留
+    pub convergence: f64, // 子午线收敛角 / °，网格北相对真北顺时针为正
+    pub scale: f64,       // 点比例因子
+}
+
+impl Grid {
+    /// 将相对真北的航向转换为相对网格北的航向 / °
+    #[inline]
+    pub fn grid_heading(&self, heading: f64) -> f64 {
+        (heading - self.convergence).rem_euclid(360.0)
+    }
+}
+
+/// 横轴墨卡托投影，支持 UTM 和高斯-克吕格
+///
+/// UTM 不处理挪威和斯瓦尔巴群岛的特殊分带，需要时用 [`Projector::with_zone`] 指定。
+#[derive(Clone, Debug)]
+pub struct Projector {
+    ellipsoid: Ellipsoid,
+    zoning: Zoning,
+    zone: Option<u8>,
+    false_easting: f64,
+    zone_prefix: bool,
+    series: Series,
+}
+
+impl Projector {
+    /// WGS84 椭球上的 UTM 投影，自动分带
+    pub fn utm() -> Self {
+        Self::new(WGS84, Zoning::Utm)
+    }
+
+    /// CGCS2000 椭球上的高斯-克吕格投影，自动分带
+    pub fn gauss_kruger(zoning: Zoning) -> Self {
+        Self::new(CGCS2000, zoning)
+    }
+
+    pub fn new(ellipsoid: Ellipsoid, zoning: Zoning) -> Self {
+        Self {
+            ellipsoid,
+            zoning,
+            zone: None,
+            false_easting: 500_000.0,
+            zone_prefix: false,
+            series: Series::new(ellipsoid),
+        }
+    }
+
+    /// 强制使用某一带，不论经度落在哪一带
+    #[inline]
+    pub fn with_zone(mut self, zone: u8) -> Self {
+        self.zone = Some(zone);
+        self
+    }
+
+    /// 设置东伪偏移，默认 500 km
+    #[inline]
+    pub fn with_false_easting(mut self, false_easting: f64) -> Self {
+        self.false_easting = false_easting;
+        self
+    }
+
+    /// 东坐标前是否加带号（即加上带号 × 1000 km），国内测绘成果常用
+    #[inline]
+    pub fn with_zone_prefix(mut self, zone_prefix: bool) -> Self {
+        self.zone_prefix = zone_prefix;
+        self
+    }
+
+    #[inline]
+    pub fn ellipsoid(&self) -> Ellipsoid {
+        self.ellipsoid
+    }
+
+    /// 经度所在的带号
+    pub fn zone_of(&self, longitude: f64) -> u8 {
+        (match self.zoning {
+            Zoning::Utm => ((longitude + 180.0) / 6.0).floor().rem_euclid(60.0),
+            Zoning::Gk6 => (longitude / 6.0).floor().rem_euclid(60.0),
+            Zoning::Gk3 => ((longitude - 1.5) / 3.0).floor().rem_euclid(120.0),
+        }) as u8
+            + 1
+    }
+
+    /// 带的中央子午线 / °
+    pub fn central_meridian(&self, zone: u8) -> f64 {
+        let zone = zone as f64;
+        match self.zoning {
+            Zoning::Utm => zone * 6.0 - 183.0,
+            Zoning::Gk6 => zone * 6.0 - 3.0,
+            Zoning::Gk3 => zone * 3.0,
+        }
+    }
+
+    #[inline]
+    fn k0(&self) -> f64 {
+        match self.zoning {
+            Zoning::Utm => 0.9996,
+            Zoning::Gk6 | Zoning::Gk3 => 1.0,
+        }
+    }
+
+    fn offset(&self, zone: u8, south: bool) -> (f64, f64) {
+        let prefix = if self.zone_prefix {
+            zone as f64 * 1e6
+        } else {
+            0.0
+        };
+        let false_northing = if south { 10_000_000.0 } else { 0.0 };
+        (self.false_easting + prefix, false_northing)
+    }
+
+    /// 正算
+    pub fn project(&self, lla: &Lla) -> Grid {
+        let zone = self.zone.unwrap_or_else(|| self.zone_of(lla.longitude));
+        let south = self.zoning == Zoning::Utm && lla.latitude < 0.0;
+        let lambda = (lla.longitude - self.central_meridian(zone)).to_radians();
+        let (x, y, convergence, scale) = self.series.forward(lla.latitude_rad(), lambda);
+        let k0 = self.k0();
+        let (fe, fn_) = self.offset(zone, south);
+        Grid {
+            zone,
+            south,
+            easting: k0 * x + fe,
+            northing: k0 * y + fn_,
+            altitude: lla.altitude,
+            convergence: convergence.to_degrees(),
+            scale: k0 * scale,
+        }
+    }
+
+    /// 将语句中的位置投影，没有有效定位时返回 [`None`]
+    #[inline]
+    pub fn project_position(&self, fix: &impl Position) -> Option<Grid> {
+        Some(self.project(&fix.lla()?))
+    }
+
+    /// 反算，忽略 `grid` 中的收敛角和比例因子
+    pub fn unproject(&self, grid: &Grid) -> Lla {
+        let k0 = self.k0();
+        let (fe, fn_) = self.offset(grid.zone, grid.south);
+        let (latitude, lambda) = self
+            .series
+            .inverse((grid.easting - fe) / k0, (grid.northing - fn_) / k0);
+        Lla {
+            latitude: latitude.to_degrees(),
+            longitude: lambda.to_degrees() + self.central_meridian(grid.zone),
+            altitude: grid.altitude,
+        }
+    }
+}
+
+/// Krüger 级数的系数，展开到 n^6，距中央子午线数千千米内误差在毫米级
+#[derive(Clone, Debug)]
+struct Series {
+    e: f64,
+    a: f64,     // 子午线弧长系数，即 A
+    ratio: f64, // A / a
+    alpha: [f64; 6],
+    beta: [f64; 6],
+}
+
+impl Series {
+    fn new(Ellipsoid { a, f }: Ellipsoid) -> Self {
+        let n = f / (2.0 - f);
+        let [n2, n3, n4, n5, n6] = [n.powi(2), n.powi(3), n.powi(4), n.powi(5), n.powi(6)];
+        let big_a = a / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0 + n6 / 256.0);
+        Self {
+            e: (f * (2.0 - f)).sqrt(),
+            a: big_a,
+            ratio: big_a / a,
+            alpha: [
+                n / 2.0 - 2.0 / 3.0 * n2 + 5.0 / 16.0 * n3 + 41.0 / 180.0 * n4 - 127.0 / 288.0 * n5
+                    + 7891.0 / 37800.0 * n6,
+                13.0 / 48.0 * n2 - 3.0 / 5.0 * n3 + 557.0 / 1440.0 * n4 + 281.0 / 630.0 * n5
+                    - 1983433.0 / 1935360.0 * n6,
+                61.0 / 240.0 * n3 - 103.0 / 140.0 * n4
+                    + 15061.0 / 26880.0 * n5
+                    + 167603.0 / 181440.0 * n6,
+                49561.0 / 161280.0 * n4 - 179.0 / 168.0 * n5 + 6601661.0 / 7257600.0 * n6,
+                34729.0 / 80640.0 * n5 - 3418889.0 / 1995840.0 * n6,
+                212378941.0 / 319334400.0 * n6,
+            ],
+            beta: [
+                n / 2.0 - 2.0 / 3.0 * n2 + 37.0 / 96.0 * n3 - 1.0 / 360.0 * n4 - 81.0 / 512.0 * n5
+                    + 96199.0 / 604800.0 * n6,
+                1.0 / 48.0 * n2 + 1.0 / 15.0 * n3 - 437.0 / 1440.0 * n4 + 46.0 / 105.0 * n5
+                    - 1118711.0 / 3870720.0 * n6,
+                17.0 / 480.0 * n3 - 37.0 / 840.0 * n4 - 209.0 / 4480.0 * n5 + 5569.0 / 90720.0 * n6,
+                4397.0 / 161280.0 * n4 - 11.0 / 504.0 * n5 - 830251.0 / 7257600.0 * n6,
+                4583.0 / 161280.0 * n5 - 108847.0 / 3991680.0 * n6,
+                20648693.0 / 638668800.0 * n6,
+            ],
+        }
+    }
+
+    /// 等角纬度的正切
+    #[inline]
+    fn tau_prime(&self, tau: f64) -> f64 {
+        let sigma = (self.e * (self.e * tau / (1.0 + tau * tau).sqrt()).atanh()).sinh();
+        tau * (1.0 + sigma * sigma).sqrt() - sigma * (1.0 + tau * tau).sqrt()
+    }
+
+    /// 返回未加比例因子和伪偏移的 (x, y, 收敛角, 比例因子)
+    fn forward(&self, phi: f64, lambda: f64) -> (f64, f64, f64, f64) {
+        let tau = phi.tan();
+        let tau_p = self.tau_prime(tau);
+        let cos_lambda = lambda.cos();
+        let xi_p = tau_p.atan2(cos_lambda);
+        let eta_p = (lambda.sin() / (tau_p * tau_p + cos_lambda * cos_lambda).sqrt()).asinh();
+
+        let (mut xi, mut eta, mut p, mut q) = (xi_p, eta_p, 1.0, 0.0);
+        for (j, alpha) in self.alpha.iter().enumerate() {
+            let j2 = 2.0 * (j + 1) as f64;
+            let (sin, cos) = (j2 * xi_p).sin_cos();
+            let (sinh, cosh) = ((j2 * eta_p).sinh(), (j2 * eta_p).cosh());
+            xi += alpha * sin * cosh;
+            eta += alpha * cos * sinh;
+            p += j2 * alpha * cos * cosh;
+            q += j2 * alpha * sin * sinh;
+        }
+
+        let gamma = (tau_p / (1.0 + tau_p * tau_p).sqrt() * lambda.tan()).atan() + q.atan2(p);
+        let sin_phi = phi.sin();
+        let k = (1.0 - self.e * self.e * sin_phi * sin_phi).sqrt() * (1.0 + tau * tau).sqrt()
+            / (tau_p * tau_p + cos_lambda * cos_lambda).sqrt()
+            * self.ratio
+            * p.hypot(q);
+        (self.a * eta, self.a * xi, gamma, k)
+    }
+
+    /// 返回 (纬度, 相对中央子午线的经度)，单位 rad
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let (xi, eta) = (y / self.a, x / self.a);
+        let (mut xi_p, mut eta_p) = (xi, eta);
+        for (j, beta) in self.beta.iter().enumerate() {
+            let j2 = 2.0 * (j + 1) as f64;
+            xi_p -= beta * (j2 * xi).sin() * (j2 * eta).cosh();
+            eta_p -= beta * (j2 * xi).cos() * (j2 * eta).sinh();
+        }
+
+        let sinh_eta_p = eta_p.sinh();
+        let (sin_xi_p, cos_xi_p) = xi_p.sin_cos();
+        let tau_p = sin_xi_p / (sinh_eta_p * sinh_eta_p + cos_xi_p * cos_xi_p).sqrt();
+        // 牛顿迭代求纬度的正切
+        let e2 = self.e * self.e;
+        let mut tau = tau_p;
+        for _ in 0..8 {
+            let tau_i = self.tau_prime(tau);
+            let delta = (tau_p - tau_i) / (1.0 + tau_i * tau_i).sqrt()
+                * (1.0 + (1.0 - e2) * tau * tau)
+                / ((1.0 - e2) * (1.0 + tau * tau).sqrt());
+            tau += delta;
+            if delta.abs() < 1e-12 {
+                break;
+            }
+        }
+        (tau.atan(), sinh_eta_p.atan2(cos_xi_p))
+    }
+}
+
+#[test]
+fn test_projection() {
+    // 参考值由 Snyder 级数独立算出
+    let eiffel = Lla {
+        latitude: 48.8583,
+        longitude: 2.2945,
+        altitude: 300.0,
+    };
+    let utm = Projector::utm();
+    let grid = utm.project(&eiffel);
+    assert_eq!((grid.zone, grid.south), (31, false));
+    assert!((grid.easting - 448251.898).abs() < 1e-2);
+    assert!((grid.northing - 5411943.794).abs() < 1e-2);
+    let back = utm.unproject(&grid);
+    assert!((back.latitude - eiffel.latitude).abs() < 1e-9);
+    assert!((back.longitude - eiffel.longitude).abs() < 1e-9);
+    assert_eq!(back.altitude, 300.0);
+
+    // 南半球
+    let sydney = Lla {
+        latitude: -33.8568,
+        longitude: 151.2153,
+        altitude: 0.0,
+    };
+    let grid = utm.project(&sydney);
+    assert_eq!((grid.zone, grid.south), (56, true));
+    assert!((grid.easting - 334900.570).abs() < 1e-2);
+    assert!((grid.northing - 6252288.753).abs() < 1e-2);
+
+    // 中央子午线上没有收敛角，东坐标等于伪偏移
+    let gk = Projector::gauss_kruger(Zoning::Gk6).with_zone_prefix(true);
+    let grid = gk.project(&Lla {
+        latitude: 39.9,
+        longitude: 117.0,
+        altitude: 0.0,
+    });
+    assert_eq!(grid.zone, 20);
+    assert!((grid.easting - 20_500_000.0).abs() < 1e-6);
+    assert!(grid.convergence.abs() < 1e-9);
+    assert!((grid.scale - 1.0).abs() < 1e-12);
+
+    // 3° 带，强制分带
+    let gk3 = Projector::gauss_kruger(Zoning::Gk3);
+    assert_eq!(gk3.zone_of(116.3269623), 39);
+    let beijing = Lla {
+        latitude: 39.9926157,
+        longitude: 116.3269623,
+        altitude: 0.0,
+    };
+    let grid = gk3.with_zone(38).project(&beijing);
+    assert_eq!(grid.zone, 38);
+    assert!(grid.convergence > 0.0);
+    assert!((grid.grid_heading(90.0) - (90.0 - grid.convergence)).abs() < 1e-12);
+    let back = Projector::gauss_kruger(Zoning::Gk3)
+        .with_zone(38)
+        .unproject(&grid);
+    assert!((back.longitude - 116.3269623).abs() < 1e-9);
+}
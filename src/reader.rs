@@ -0,0 +1,135 @@
+﻿use super::{NmeaParser, ParsedSentence};
+use std::io::{ErrorKind, Read, Result};
+
+/// 从 [`Read`] 读取并解析 NMEA 语句的阻塞迭代器
+///
+/// 文件、TCP 流、串口等都可以直接使用。
+/// 读到 EOF 时迭代结束，之后再次调用 [`Iterator::next`] 会重新尝试读取；
+/// [`ErrorKind::Interrupted`] 会自动重试，其他错误（包括非阻塞源的 [`ErrorKind::WouldBlock`]）原样交给调用者，
+/// 调用者可以在处理后继续迭代，已缓存的数据不会丢失。
+pub struct NmeaReader<R, const LEN: usize = 256> {
+    inner: R,
+    parser: NmeaParser<LEN>,
+}
+
+impl<R: Read, const LEN: usize> NmeaReader<R, LEN> {
+    /// 使用默认设置的解析器
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self::with_parser(inner, Default::default())
+    }
+
+    /// 使用设置好分帧、校验和、解析模式等规则的解析器
+    #[inline]
+    pub fn with_parser(inner: R, parser: NmeaParser<LEN>) -> Self {
+        Self { inner, parser }
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// 取回数据源，缓冲区中未解析的数据会被丢弃
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read, const LEN: usize> Iterator for NmeaReader<R, LEN> {
+    type Item = Result<ParsedSentence>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            // 先取尽缓冲区中的语句，解析器会在缓冲区满时丢弃字节，因此取尽后一定有空间
+            if let Some(sentence) = self.parser.diagnostics().find_map(|event| event.ok()) {
+                return Some(Ok(sentence));
+            }
+            let buf = self.parser.as_buf();
+            if buf.is_empty() {
+                continue;
+            }
+            match self.inner.read(buf) {
+                Ok(0) => return None,
+                Ok(n) => self.parser.notify_received(n),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reader() {
+    use super::{ChecksumPolicy, ChecksumRules, NmeaLine};
+    use std::io::Error;
+
+    /// 每次只给出几个字节，并穿插中断和阻塞
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            self.1 += 1;
+            match self.1 % 4 {
+                1 => Err(Error::from(ErrorKind::Interrupted)),
+                3 => Err(Error::from(ErrorKind::WouldBlock)),
+                _ => {
+                    let n = self.0.len().min(buf.len()).min(7);
+                    buf[..n].copy_from_slice(&self.0[..n]);
+                    self.0 = &self.0[n..];
+                    Ok(n)
+                }
+            }
+        }
+    }
+
+    const STREAM: &[u8] =
+        b"xx$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n\
+$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4D\r\n\
+$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n";
+
+    // 缓冲区比一条语句还短时不会死锁
+    let mut reader = NmeaReader::<_, 32>::new(Trickle(STREAM, 0));
+    let mut would_block = 0;
+    for item in &mut reader {
+        match item {
+            Ok(_) => panic!(),
+            Err(e) => {
+                assert_eq!(e.kind(), ErrorKind::WouldBlock);
+                would_block += 1;
+            }
+        }
+    }
+    assert!(would_block > 0);
+
+    let mut reader = NmeaReader::<_>::new(Trickle(STREAM, 0));
+    let lines = reader
+        .by_ref()
+        .filter_map(|item| item.ok())
+        .map(|sentence| sentence.line)
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| matches!(line, NmeaLine::GTIMU(_))));
+    assert!(!matches!(reader.next(), Some(Ok(_))));
+    assert!(reader.get_ref().0.is_empty());
+
+    // 不检查校验和时校验和错误的语句也会解析出来
+    let parser = NmeaParser::default().with_checksum(ChecksumRules::new(ChecksumPolicy::Ignored));
+    let reader = NmeaReader::<_>::with_parser(Trickle(STREAM, 0), parser);
+    assert_eq!(reader.filter_map(|item| item.ok()).count(), 3);
+
+    // 超过 TEXT_CAPACITY 的未知语句原样保留
+    let long = crate::encode_nmea("PXYZ", &"1,".repeat(80));
+    let lines = NmeaReader::<_, 512>::new(long.as_bytes())
+        .map(|item| item.unwrap().line)
+        .collect::<Vec<_>>();
+    assert!(
+        matches!(&lines[..], [NmeaLine::Unknown(head, tail)] if head == "PXYZ" && tail.len() == 160)
+    );
+}
@@ -0,0 +1,256 @@
+﻿use super::{BodyParseError, NmeaLineRef, ParseMode};
+use core::{any::Any, fmt};
+
+/// 可以注册到 [`SentenceRegistry`] 的语句体，用于在本库之外支持厂商自定义的语句
+pub trait SentenceBody:
+    Clone + PartialEq + fmt::Debug + fmt::Display + Send + Sync + 'static
+{
+    /// 语句头，不含 `$`，如 `PSTAT`
+    const HEAD: &'static str;
+
+    /// 是否处理语句头为 `head` 的语句，默认要求与 [`HEAD`](Self::HEAD) 相同
+    #[inline]
+    fn matches(head: &str) -> bool {
+        head == Self::HEAD
+    }
+
+    /// 解析语句头和 `,` 之后的部分
+    fn parse_with(s: &str, mode: ParseMode) -> Result<Self, BodyParseError>;
+}
+
+/// 对象安全的 [`SentenceBody`]
+trait DynBody: fmt::Debug + fmt::Display + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    fn clone_box(&self) -> Box<dyn DynBody>;
+    fn eq_dyn(&self, other: &dyn DynBody) -> bool;
+}
+
+impl<T: SentenceBody> DynBody for T {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    #[inline]
+    fn clone_box(&self) -> Box<dyn DynBody> {
+        Box::new(self.clone())
+    }
+
+    #[inline]
+    fn eq_dyn(&self, other: &dyn DynBody) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+/// 注册的语句解析出的语句体，用 [`downcast_ref`](Self::downcast_ref) 取回具体类型
+pub struct CustomBody(Box<dyn DynBody>);
+
+impl CustomBody {
+    #[inline]
+    pub fn new<T: SentenceBody>(body: T) -> Self {
+        Self(Box::new(body))
+    }
+
+    #[inline]
+    pub fn is<T: SentenceBody>(&self) -> bool {
+        self.0.as_any().is::<T>()
+    }
+
+    #[inline]
+    pub fn downcast_ref<T: SentenceBody>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref()
+    }
+
+    /// 取出具体类型，类型不符时原样返回
+    #[inline]
+    pub fn downcast<T: SentenceBody>(self) -> Result<T, Self> {
+        if self.is::<T>() {
+            Ok(*self.0.into_any().downcast().expect("type checked"))
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Clone for CustomBody {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl PartialEq for CustomBody {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_dyn(&*other.0)
+    }
+}
+
+impl fmt::Debug for CustomBody {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for CustomBody {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    matches: fn(&str) -> bool,
+    parse: fn(&str, ParseMode) -> Result<CustomBody, BodyParseError>,
+}
+
+/// 运行时注册的语句表，解析器用它识别本库之外的语句
+///
+/// 按注册顺序查找语句头匹配的语句，都不匹配才按内置的语句解析，因此注册的语句可以覆盖内置的同名语句。
+#[derive(Clone, Debug, Default)]
+pub struct SentenceRegistry {
+    entries: Vec<Entry>,
+}
+
+impl SentenceRegistry {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn with<T: SentenceBody>(mut self) -> Self {
+        self.register::<T>();
+        self
+    }
+
+    pub fn register<T: SentenceBody>(&mut self) {
+        self.entries.push(Entry {
+            matches: T::matches,
+            parse: |s, mode| T::parse_with(s, mode).map(CustomBody::new),
+        });
+    }
+
+    /// 按指定模式解析去掉 `$` 和校验和的语句，注册的语句解析为 [`NmeaLineRef::Custom`]
+    pub fn parse<'a>(
+        &self,
+        s: &'a str,
+        mode: ParseMode,
+    ) -> Result<NmeaLineRef<'a>, BodyParseError> {
+        let (head, tail) = s.split_once(',').unwrap_or((s, ""));
+        match self.entries.iter().find(|entry| (entry.matches)(head)) {
+            Some(entry) => Ok(NmeaLineRef::Custom(head, (entry.parse)(tail, mode)?)),
+            None => NmeaLineRef::parse_with(s, mode),
+        }
+    }
+}
+
+#[test]
+fn test_registry() {
+    use crate::{gphpd, NmeaLine, NmeaParser};
+
+    /// 厂商自定义的状态语句
+    #[derive(Clone, PartialEq, Debug)]
+    struct Stat {
+        temperature: i16,
+        voltage: crate::Fixed,
+    }
+
+    impl SentenceBody for Stat {
+        const HEAD: &'static str = "PSTAT";
+
+        fn parse_with(s: &str, _: ParseMode) -> Result<Self, BodyParseError> {
+            let mut s = s.split(',');
+            Ok(Self {
+                temperature: parse_field!(s => "PSTAT:Temperature"),
+                voltage: parse_field!(s => "PSTAT:Voltage"),
+            })
+        }
+    }
+
+    impl fmt::Display for Stat {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{},{}", self.temperature, self.voltage)
+        }
+    }
+
+    /// 覆盖内置的 GPHPD
+    #[derive(Clone, PartialEq, Debug)]
+    struct Hpd(String);
+
+    impl SentenceBody for Hpd {
+        const HEAD: &'static str = "GPHPD";
+
+        fn parse_with(s: &str, _: ParseMode) -> Result<Self, BodyParseError> {
+            Ok(Self(s.into()))
+        }
+    }
+
+    impl fmt::Display for Hpd {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    const STAT: &str = "PSTAT,-12,3.30";
+    const HPD: &str =
+        "GPHPD,0,0.000,0.000,0.000,0.000,0.0000000,0.0000000,0.00,0.000,0.000,0.000,0.000,0,0,00";
+
+    // 没有注册时是未知语句
+    let empty = SentenceRegistry::new();
+    assert!(matches!(
+        empty.parse(STAT, ParseMode::Strict),
+        Ok(NmeaLineRef::Unknown("PSTAT", "-12,3.30"))
+    ));
+    assert!(matches!(
+        empty.parse(HPD, ParseMode::Strict),
+        Ok(NmeaLineRef::GPHPD(gphpd::Body { .. }))
+    ));
+
+    let registry = SentenceRegistry::new().with::<Stat>();
+    let line = registry.parse(STAT, ParseMode::Strict).unwrap();
+    let NmeaLineRef::Custom("PSTAT", body) = &line else {
+        panic!("{:?}", line);
+    };
+    assert!(body.is::<Stat>());
+    assert!(!body.is::<Hpd>());
+    assert_eq!(body.downcast_ref::<Stat>().unwrap().temperature, -12);
+    assert_eq!(line.to_string(), format!("${}*72\r\n", STAT));
+    assert_eq!(
+        registry.parse("PSTAT,hot,3.30", ParseMode::Strict),
+        Err(BodyParseError::ParseFailed(
+            "PSTAT:Temperature",
+            "hot".into()
+        ))
+    );
+
+    // 解析器先查找注册的语句，注册的 GPHPD 覆盖内置的
+    let registry = registry.with::<Hpd>();
+    let mut parser = NmeaParser::<256>::default().with_registry(registry);
+    let stream = format!("${}*72\r\n{}", STAT, NmeaLineRef::parse(HPD).unwrap());
+    parser.as_buf()[..stream.len()].copy_from_slice(stream.as_bytes());
+    parser.notify_received(stream.len());
+    let Some((NmeaLine::Custom(head, body), _)) = parser.next() else {
+        panic!();
+    };
+    assert_eq!(head, "PSTAT");
+    assert_eq!(
+        body.clone().downcast::<Stat>(),
+        Ok(Stat {
+            temperature: -12,
+            voltage: crate::Fixed::new(330, 2),
+        })
+    );
+    assert!(body.downcast::<Hpd>().is_err());
+    let sentence = parser.next_ref().unwrap();
+    assert!(matches!(sentence.line, NmeaLineRef::Custom("GPHPD", ref body) if body.is::<Hpd>()));
+    assert_eq!(parser.next(), None);
+}
@@ -0,0 +1,102 @@
+﻿use core::{fmt, str::FromStr};
+
+/// 发送者标识，即语句头的前两个字符
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Talker {
+    GP,             // GPS
+    GL,             // GLONASS
+    GA,             // Galileo
+    GB,             // 北斗
+    BD,             // 北斗，旧式写法
+    GQ,             // QZSS
+    GI,             // NavIC
+    GN,             // 多系统联合
+    Other([u8; 2]), // 其他
+}
+
+impl Talker {
+    #[inline]
+    pub fn as_bytes(&self) -> [u8; 2] {
+        use Talker::*;
+        match self {
+            GP => *b"GP",
+            GL => *b"GL",
+            GA => *b"GA",
+            GB => *b"GB",
+            BD => *b"BD",
+            GQ => *b"GQ",
+            GI => *b"GI",
+            GN => *b"GN",
+            Other(bytes) => *bytes,
+        }
+    }
+}
+
+impl FromStr for Talker {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Talker::*;
+        match s.as_bytes() {
+            [a, b] if a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() => {
+                Ok(match [*a, *b] {
+                    [b'G', b'P'] => GP,
+                    [b'G', b'L'] => GL,
+                    [b'G', b'A'] => GA,
+                    [b'G', b'B'] => GB,
+                    [b'B', b'D'] => BD,
+                    [b'G', b'Q'] => GQ,
+                    [b'G', b'I'] => GI,
+                    [b'G', b'N'] => GN,
+                    other => Other(other),
+                })
+            }
+            [..] => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Talker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b] = self.as_bytes();
+        write!(f, "{}{}", a as char, b as char)
+    }
+}
+
+/// 将语句头拆分为发送者和语句类型
+#[inline]
+pub(crate) fn split_head(head: &str) -> Option<(Talker, &str)> {
+    if head.len() == 5 && head.is_char_boundary(2) {
+        let (talker, formatter) = head.split_at(2);
+        Some((talker.parse().ok()?, formatter))
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_talker_agnostic() {
+    use crate::NmeaLine;
+
+    const LINE: &str = "060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,";
+    for talker in ["GP", "GN", "BD", "GB"] {
+        let line = format!("{}GGA,{}", talker, LINE)
+            .parse::<NmeaLine>()
+            .unwrap();
+        match line {
+            NmeaLine::GGA(t, _, tail) => {
+                assert_eq!(t.to_string(), talker);
+                assert_eq!(tail, LINE);
+            }
+            _ => panic!("Parse failed."),
+        }
+    }
+    assert!(matches!(
+        "GNGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1,1".parse(),
+        Ok(NmeaLine::GSA(Talker::GN, _))
+    ));
+    assert!(matches!(
+        "XXTXT,1".parse(),
+        Ok(NmeaLine::Unknown(head, _)) if head == "XXTXT"
+    ));
+}
@@ -0,0 +1,311 @@
+﻿use super::Fixed;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// GPS 时间起点 1980-01-06 00:00:00 UTC 的 Unix 时间 / s
+const GPS_EPOCH: i64 = 315_964_800;
+/// TAI - GPS / s
+const TAI_GPS: i64 = 19;
+const MS_PER_DAY: i64 = 86_400_000;
+const MS_PER_WEEK: i64 = 7 * MS_PER_DAY;
+
+/// 公历日期
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// UTC 时间点，自 1970-01-01 00:00:00 UTC 起的毫秒数，不计闰秒（即 Unix 时间）
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct UtcTime(pub i64);
+
+/// TAI 时间点，Unix 时间加上当时的 TAI - UTC，单位毫秒
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TaiTime(pub i64);
+
+#[cfg(feature = "std")]
+/// 闰秒表，可以在新的闰秒公布后追加
+#[derive(Clone, Debug)]
+pub struct LeapSeconds(Vec<(i64, i32)>); // (生效时刻的 Unix 时间 / s, 生效后的 TAI - UTC / s)
+
+/// 携带 GPS 周和周内时间的语句
+pub trait GpsTimed {
+    /// GPS 周和周内毫秒数
+    fn gps_week_ms(&self) -> (u16, u32);
+
+    #[inline]
+    fn tai(&self) -> TaiTime {
+        let (week, ms) = self.gps_week_ms();
+        gps_to_tai(week, ms)
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn utc(&self, leap_seconds: &LeapSeconds) -> UtcTime {
+        leap_seconds.tai_to_utc(self.tai())
+    }
+}
+
+impl Date {
+    /// 解析 RMC 中 `ddmmyy` 格式的日期，`80..=99` 视为 19xx 年
+    pub fn from_ddmmyy(ddmmyy: u32) -> Option<Self> {
+        let yy = (ddmmyy % 100) as u16;
+        Self::new(
+            if yy >= 80 { 1900 + yy } else { 2000 + yy },
+            (ddmmyy / 100 % 100) as u8,
+            (ddmmyy / 10000) as u8,
+        )
+    }
+
+    /// 日期不存在时返回 [`None`]，如 2 月 30 日、非闰年的 2 月 29 日
+    #[inline]
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) {
+            Some(Self { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// 自 1970-01-01 起的天数
+    fn days_since_epoch(&self) -> i64 {
+        let (m, d) = (self.month as i64, self.day as i64);
+        let y = self.year as i64 - if m <= 2 { 1 } else { 0 };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    /// 由自 1970-01-01 起的天数构造
+    fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as u16;
+        Self { year, month, day }
+    }
+}
+
+/// 将 `hhmmss.sss` 格式的时刻转换为当天的毫秒数
+///
+/// 小数位数不限，毫秒以下的部分截断。
+pub fn time_of_day_ms(hhmmss: Fixed) -> Option<u32> {
+    let hhmmss_sss = u32::try_from(hhmmss.rescale(3)?.value()).ok()?;
+    let hh = hhmmss_sss / 10_000_000;
+    let mm = hhmmss_sss / 100_000 % 100;
+    let ss = hhmmss_sss % 100_000;
+    // 允许闰秒 23:59:60
+    if hh < 24 && mm < 60 && ss < 61_000 {
+        Some((hh * 60 + mm) * 60_000 + ss)
+    } else {
+        None
+    }
+}
+
+impl UtcTime {
+    /// 由日期和 `hhmmss.sss` 格式的时刻构造
+    pub fn from_date_time(date: Date, hhmmss: Fixed) -> Option<Self> {
+        Some(Self(
+            date.days_since_epoch() * MS_PER_DAY + time_of_day_ms(hhmmss)? as i64,
+        ))
+    }
+
+    /// 取与当前时间最近的、时刻为 `hhmmss.sss` 的时间点
+    ///
+    /// 用于给只有时刻的语句（如 GGA）补上日期，能正确处理零点前后日期不一致的情况。
+    pub fn with_time_of_day(self, hhmmss: Fixed) -> Option<Self> {
+        let time = self.0.div_euclid(MS_PER_DAY) * MS_PER_DAY + time_of_day_ms(hhmmss)? as i64;
+        Some(Self(if time - self.0 > MS_PER_DAY / 2 {
+            time - MS_PER_DAY
+        } else if self.0 - time > MS_PER_DAY / 2 {
+            time + MS_PER_DAY
+        } else {
+            time
+        }))
+    }
+
+    #[inline]
+    pub fn date(self) -> Date {
+        Date::from_days_since_epoch(self.0.div_euclid(MS_PER_DAY))
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn to_system_time(self) -> SystemTime {
+        if self.0 >= 0 {
+            UNIX_EPOCH + Duration::from_millis(self.0 as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_millis(self.0.unsigned_abs())
+        }
+    }
+}
+
+/// GPS 周和周内毫秒数转换为 TAI，周内时间超过一周时自然进位
+#[inline]
+pub fn gps_to_tai(week: u16, ms: u32) -> TaiTime {
+    TaiTime((GPS_EPOCH + TAI_GPS) * 1000 + week as i64 * MS_PER_WEEK + ms as i64)
+}
+
+/// 将只有 10 位的 GPS 周（模 1024）展开为距离 `reference` 最近的完整周数
+pub fn unroll_week(week: u16, reference: u16) -> u16 {
+    let base = reference as i32 - (reference % 1024) as i32 + (week % 1024) as i32;
+    [base - 1024, base, base + 1024]
+        .into_iter()
+        .filter(|w| (0..=u16::MAX as i32).contains(w))
+        .min_by_key(|w| (w - reference as i32).abs())
+        .unwrap() as u16
+}
+
+#[cfg(feature = "std")]
+impl Default for LeapSeconds {
+    /// 截至 2017-01-01 的闰秒表
+    fn default() -> Self {
+        Self(vec![
+            (63072000, 10),   // 1972-01-01
+            (78796800, 11),   // 1972-07-01
+            (94694400, 12),   // 1973-01-01
+            (126230400, 13),  // 1974-01-01
+            (157766400, 14),  // 1975-01-01
+            (189302400, 15),  // 1976-01-01
+            (220924800, 16),  // 1977-01-01
+            (252460800, 17),  // 1978-01-01
+            (283996800, 18),  // 1979-01-01
+            (315532800, 19),  // 1980-01-01
+            (362793600, 20),  // 1981-07-01
+            (394329600, 21),  // 1982-07-01
+            (425865600, 22),  // 1983-07-01
+            (489024000, 23),  // 1985-07-01
+            (567993600, 24),  // 1988-01-01
+            (631152000, 25),  // 1990-01-01
+            (662688000, 26),  // 1991-01-01
+            (709948800, 27),  // 1992-07-01
+            (741484800, 28),  // 1993-07-01
+            (773020800, 29),  // 1994-07-01
+            (820454400, 30),  // 1996-01-01
+            (867715200, 31),  // 1997-07-01
+            (915148800, 32),  // 1999-01-01
+            (1136073600, 33), // 2006-01-01
+            (1230768000, 34), // 2009-01-01
+            (1341100800, 35), // 2012-07-01
+            (1435708800, 36), // 2015-07-01
+            (1483228800, 37), // 2017-01-01
+        ])
+    }
+}
+
+#[cfg(feature = "std")]
+impl LeapSeconds {
+    /// 追加或修改一条记录：自 `since` 起 TAI - UTC 为 `tai_utc` 秒
+    pub fn insert(&mut self, since: UtcTime, tai_utc: i32) {
+        let since = since.0.div_euclid(1000);
+        match self.0.binary_search_by_key(&since, |(t, _)| *t) {
+            Ok(i) => self.0[i].1 = tai_utc,
+            Err(i) => self.0.insert(i, (since, tai_utc)),
+        }
+    }
+
+    /// `utc` 时刻的 TAI - UTC / s
+    pub fn tai_utc(&self, utc: UtcTime) -> i32 {
+        self.0
+            .iter()
+            .rev()
+            .find(|(t, _)| t * 1000 <= utc.0)
+            .map_or(0, |(_, dt)| *dt)
+    }
+
+    #[inline]
+    pub fn utc_to_tai(&self, utc: UtcTime) -> TaiTime {
+        TaiTime(utc.0 + self.tai_utc(utc) as i64 * 1000)
+    }
+
+    pub fn tai_to_utc(&self, tai: TaiTime) -> UtcTime {
+        let dt = self
+            .0
+            .iter()
+            .rev()
+            .find(|(t, dt)| (t + *dt as i64) * 1000 <= tai.0)
+            .map_or(0, |(_, dt)| *dt);
+        UtcTime(tai.0 - dt as i64 * 1000)
+    }
+
+    #[inline]
+    pub fn gps_to_utc(&self, week: u16, ms: u32) -> UtcTime {
+        self.tai_to_utc(gps_to_tai(week, ms))
+    }
+}
+
+/// 某月的天数
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_time() {
+    let leap_seconds = LeapSeconds::default();
+
+    // 2021-11-22 06:02:12.400 UTC
+    let utc = leap_seconds.gps_to_utc(2185, 108150400);
+    assert_eq!(utc, UtcTime(1637560932400));
+    assert_eq!(utc.date(), Date::new(2021, 11, 22).unwrap());
+    assert_eq!(leap_seconds.utc_to_tai(utc), gps_to_tai(2185, 108150400));
+    assert_eq!(
+        UtcTime::from_date_time(Date::from_ddmmyy(221121).unwrap(), Fixed::new(6021240, 2)),
+        Some(utc)
+    );
+    // 时刻的小数位数不限
+    assert_eq!(
+        UtcTime::from_date_time(Date::from_ddmmyy(221121).unwrap(), Fixed::new(60212400, 3)),
+        Some(utc)
+    );
+    assert_eq!(
+        UtcTime::from_date_time(Date::from_ddmmyy(221121).unwrap(), Fixed::new(60212, 0)),
+        Some(UtcTime(utc.0 - 400))
+    );
+    assert_eq!(time_of_day_ms(Fixed::new(-1, 0)), None);
+    assert_eq!(time_of_day_ms(Fixed::new(246000, 0)), None);
+
+    // 按月份和闰年检查日期
+    assert_eq!(Date::new(2021, 2, 30), None);
+    assert_eq!(Date::new(2021, 2, 29), None);
+    assert_eq!(Date::new(2021, 4, 31), None);
+    assert_eq!(Date::new(2100, 2, 29), None);
+    assert!(Date::new(2020, 2, 29).is_some());
+    assert!(Date::new(2000, 2, 29).is_some());
+    assert!(Date::new(2021, 12, 31).is_some());
+    assert_eq!(Date::from_ddmmyy(300221), None);
+
+    // 跨零点
+    let rmc = UtcTime::from_date_time(Date::from_ddmmyy(311221).unwrap(), Fixed::new(23595990, 2))
+        .unwrap();
+    let gga = rmc.with_time_of_day(Fixed::new(10, 2)).unwrap();
+    assert_eq!(gga.date(), Date::new(2022, 1, 1).unwrap());
+    assert_eq!(gga.0 - rmc.0, 200);
+    assert_eq!(gga.with_time_of_day(Fixed::new(23595990, 2)), Some(rmc));
+
+    // 周翻转
+    assert_eq!(unroll_week(2185 % 1024, 2100), 2185);
+    assert_eq!(unroll_week(1, 2047), 2049);
+    assert_eq!(gps_to_tai(2185, 7 * 86_400_000), gps_to_tai(2186, 0));
+
+    // 新的闰秒
+    let mut leap_seconds = leap_seconds;
+    leap_seconds.insert(UtcTime(1830297600000), 38);
+    assert_eq!(leap_seconds.tai_utc(UtcTime(1830297600000)), 38);
+    assert_eq!(leap_seconds.tai_utc(UtcTime(1830297599999)), 37);
+}
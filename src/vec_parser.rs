@@ -0,0 +1,216 @@
+﻿use super::{
+    parser::{ChecksumRules, Cursors, Framing, ParseEvent, ParsedSentence, ParsedSentenceRef},
+    NmeaLine, ParseMode, SentenceRegistry,
+};
+
+/// 以 [`Vec`] 为缓冲区、可以增长的 NMEA 语句解析器
+///
+/// 缓冲区平时保持 `soft_max` 字节，遇到更长的语句时按需增长，该语句解析完后再缩回；
+/// 超过 `soft_max` 字节的语句照常解析，在诊断模式下先报告 [`ParseEvent::SoftLimitExceeded`]；
+/// 从 `$` 起超过 `hard_max` 字节仍未结束的语句被丢弃，在诊断模式下报告为 [`ParseEvent::Oversized`]。
+pub struct NmeaVecParser {
+    buf: Vec<u8>,
+    cursor_r: Cursors,
+    cursor_w: usize,
+    soft_max: usize,
+    hard_max: usize,
+    pending: Option<ParsedSentence>, // 已报告超过软上限、尚未产出的语句
+}
+
+impl NmeaVecParser {
+    /// 要求 `0 < soft_max <= hard_max`
+    pub fn new(soft_max: usize, hard_max: usize) -> Self {
+        assert!(0 < soft_max && soft_max <= hard_max);
+        Self {
+            buf: vec![0; soft_max],
+            cursor_r: Cursors::default(),
+            cursor_w: 0,
+            soft_max,
+            hard_max,
+            pending: None,
+        }
+    }
+
+    /// 设置分帧规则，默认为 [`Framing::Lenient`]
+    #[inline]
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.cursor_r.framing = framing;
+        self
+    }
+
+    /// 设置校验和规则，默认见 [`ChecksumRules::default`]
+    #[inline]
+    pub fn with_checksum(mut self, rules: ChecksumRules) -> Self {
+        self.cursor_r.checksum = rules;
+        self
+    }
+
+    /// 设置语句体的解析模式，默认为 [`ParseMode::Strict`]
+    #[inline]
+    pub fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.cursor_r.mode = mode;
+        self
+    }
+
+    /// 设置注册的语句，默认为空
+    #[inline]
+    pub fn with_registry(mut self, registry: SentenceRegistry) -> Self {
+        self.cursor_r.registry = registry;
+        self
+    }
+
+    /// 语句长度的软上限，也是缓冲区平时的大小
+    #[inline]
+    pub fn soft_max(&self) -> usize {
+        self.soft_max
+    }
+
+    /// 从 `$` 起的最大语句长度
+    #[inline]
+    pub fn hard_max(&self) -> usize {
+        self.hard_max
+    }
+
+    /// 准备接收，必要时挪动、增长或缩回缓冲区
+    ///
+    /// 取尽已接收的语句后调用，返回的切片一定不为空
+    pub fn as_buf(&mut self) -> &mut [u8] {
+        let r = self.cursor_r.r;
+        if r == self.cursor_w {
+            self.cursor_w = 0;
+            self.cursor_r.reset();
+        } else if r > 0 && self.free() <= self.buf.len() / 4 {
+            self.buf.copy_within(r..self.cursor_w, 0);
+            self.cursor_w -= r;
+            self.cursor_r.reset();
+        }
+        // 长语句已经解析完，缩回
+        if self.buf.len() > self.soft_max && self.cursor_w <= self.soft_max / 2 {
+            self.buf.truncate(self.soft_max);
+            self.buf.shrink_to_fit();
+        }
+        // 未解析完的语句占满了缓冲区，增长，最多容纳 `hard_max` 字节的语句和校验和
+        let cap = self.hard_max + 3;
+        if self.free() <= self.buf.len() / 4 && self.buf.len() < cap {
+            self.buf.resize((self.buf.len() * 2).min(cap), 0);
+        }
+        &mut self.buf[self.cursor_w..]
+    }
+
+    #[inline]
+    pub fn notify_received(&mut self, n: usize) {
+        self.cursor_w += n;
+    }
+
+    /// 以诊断模式迭代缓冲区，被拒绝的语句、丢弃的字节和超长的语句也会作为 [`ParseEvent`] 报告
+    #[inline]
+    pub fn diagnostics(&mut self) -> impl Iterator<Item = Result<ParsedSentence, ParseEvent>> + '_ {
+        core::iter::from_fn(move || self.parse_next())
+    }
+
+    /// 从缓冲区解析一个借用缓冲区的 NEMA 消息
+    #[inline]
+    pub fn next_ref(&mut self) -> Option<ParsedSentenceRef<'_>> {
+        let buf = &self.buf[..self.cursor_w];
+        self.cursor_r.next_ref(buf, false, self.hard_max)
+    }
+
+    /// 超过软上限的语句先报告事件，下次再产出
+    fn parse_next(&mut self) -> Option<Result<ParsedSentence, ParseEvent>> {
+        if let Some(sentence) = self.pending.take() {
+            return Some(Ok(sentence));
+        }
+        let buf = &self.buf[..self.cursor_w];
+        match self.cursor_r.parse_next(buf, false, self.hard_max)? {
+            Ok(sentence) if sentence.raw.len() > self.soft_max => {
+                let event = ParseEvent::SoftLimitExceeded {
+                    offset: sentence.offset,
+                    len: sentence.raw.len(),
+                };
+                self.pending = Some(sentence);
+                Some(Err(event))
+            }
+            result => Some(result),
+        }
+    }
+
+    #[inline]
+    fn free(&self) -> usize {
+        self.buf.len() - self.cursor_w
+    }
+}
+
+impl Iterator for NmeaVecParser {
+    type Item = (NmeaLine, u8);
+
+    /// 从缓冲区解析一个 NEMA 消息，当且仅当缓冲区中没有完整的消息时返回 [`None`]，此时需要读取新的数据填充到缓冲区
+    ///
+    /// 与 [`NmeaParser`](super::NmeaParser) 的迭代器相同，是为兼容保留的有损接口，
+    /// 新代码使用 [`NmeaVecParser::diagnostics`] 或 [`NmeaVecParser::next_ref`]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let sentence = self.next_ref()?;
+            let checksum = sentence.checksum();
+            if let Ok(line) = sentence.line.try_into_owned() {
+                break Some((line, checksum));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_vec_parser() {
+    use crate::encode_nmea;
+
+    // 每次只收到一部分
+    fn feed(
+        parser: &mut NmeaVecParser,
+        mut data: &[u8],
+    ) -> Vec<Result<ParsedSentence, ParseEvent>> {
+        let mut events = Vec::new();
+        while !data.is_empty() {
+            let buf = parser.as_buf();
+            let n = buf.len().min(data.len()).min(50);
+            buf[..n].copy_from_slice(&data[..n]);
+            parser.notify_received(n);
+            data = &data[n..];
+            events.extend(parser.diagnostics());
+        }
+        events
+    }
+
+    let long = encode_nmea("BESTPOSA", &"0,".repeat(200));
+    let mut parser = NmeaVecParser::new(128, 512);
+    let events = feed(&mut parser, long.as_bytes());
+    // 超过软上限的长日志照常解析，先报告事件
+    assert!(matches!(
+        &events[..],
+        [
+            Err(ParseEvent::SoftLimitExceeded { offset: 0, len }),
+            Ok(ParsedSentence {
+                line: NmeaLine::Unknown(head, tail),
+                ..
+            }),
+        ] if *len == long.len() - 2 && head == "BESTPOSA" && tail.len() == 400
+    ));
+    // 解析完后缩回
+    feed(&mut parser, b"\r\n");
+    assert_eq!(parser.buf.len(), 128);
+
+    // 失控的语句报告为错误，之后的语句不受影响
+    let mut stream = b"$GPXXX,".to_vec();
+    stream.extend(std::iter::repeat_n(b'0', 1000));
+    stream.extend(b"\r\n$GTIMU,0,6.000,3.3755,-0.0768,-3.0907,-0.1633,0.6105,0.7855,27.5*4C\r\n");
+    let events = feed(&mut parser, &stream);
+    assert!(matches!(events[0], Err(ParseEvent::Oversized { len }) if len > 512));
+    assert!(matches!(
+        events.last(),
+        Some(Ok(ParsedSentence {
+            line: NmeaLine::GTIMU(_),
+            received: Some(0x4C),
+            verified: true,
+            ..
+        }))
+    ));
+    assert!(parser.buf.len() <= 512 + 3);
+}